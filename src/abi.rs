@@ -0,0 +1,1063 @@
+use crate::types::{normalize_path, relative_import_path, validate_identifier};
+use crate::{actors, ghosts};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Prefix of the generated handler functions
+pub const HANDLER_PREFIX: &str = "handler_";
+
+/// A single parameter of an ABI function
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct AbiParam {
+    #[serde(default)]
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(rename = "internalType")]
+    pub internal_type: Option<String>,
+    /// The struct members of a tuple (or tuple array) parameter
+    #[serde(default)]
+    pub components: Vec<AbiParam>,
+}
+
+/// An ABI entry, only functions are kept when loading a target
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct AbiFunction {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub inputs: Vec<AbiParam>,
+    #[serde(rename = "stateMutability", default)]
+    pub state_mutability: String,
+}
+
+/// A contract to fuzz, loaded from a Foundry artifact or a bare ABI
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub name: String,
+    pub source: PathBuf,
+    pub constructor: Vec<AbiParam>,
+    pub functions: Vec<AbiFunction>,
+    /// The balance-like getters tracked by ghost variables (eg "balanceOf", "totalSupply")
+    pub getters: Vec<String>,
+}
+
+impl AbiParam {
+    /// The Solidity type to use when declaring this parameter, with its data location
+    pub fn solidity_type(&self) -> String {
+        let base = self.base_type();
+
+        if self.is_dynamic() {
+            format!("{} memory", base)
+        } else {
+            base
+        }
+    }
+
+    /// The Solidity type of this parameter, without data location (eg "string[]")
+    fn base_type(&self) -> String {
+        match &self.internal_type {
+            Some(internal) if internal.starts_with("struct ") => {
+                internal.trim_start_matches("struct ").to_string()
+            }
+            Some(internal) if internal.starts_with("enum ") => {
+                internal.trim_start_matches("enum ").to_string()
+            }
+            Some(internal) if internal.starts_with("contract ") => {
+                internal.trim_start_matches("contract ").to_string()
+            }
+            _ => self.kind.clone(),
+        }
+    }
+
+    /// The type of this parameter in a function signature, structs being spelled out as their
+    /// members (eg "(uint256,address)[]" for "Vault.Params[]")
+    pub fn canonical_type(&self) -> String {
+        match self.kind.strip_prefix("tuple") {
+            Some(suffix) => format!(
+                "({}){}",
+                self.components
+                    .iter()
+                    .map(AbiParam::canonical_type)
+                    .collect::<Vec<_>>()
+                    .join(","),
+                suffix
+            ),
+            None => self.kind.clone(),
+        }
+    }
+
+    /// A placeholder value of this parameter type, `None` if it can't be written as a literal
+    pub fn placeholder(&self) -> Option<String> {
+        let internal = self.internal_type.as_deref().unwrap_or(&self.kind);
+
+        if self.kind.starts_with("tuple") || self.kind.ends_with(']') && !self.kind.ends_with("[]")
+        {
+            return None;
+        }
+
+        // the allocated type has no data location, nested arrays included (eg "new string[][](0)")
+        Some(if self.kind.ends_with("[]") {
+            format!("new {}(0)", self.base_type())
+        } else if let Some(contract) = internal.strip_prefix("contract ") {
+            format!("{}(address(0))", contract)
+        } else if let Some(enumeration) = internal.strip_prefix("enum ") {
+            format!("{}(0)", enumeration)
+        } else if internal == "address payable" {
+            String::from("payable(address(0))")
+        } else if self.kind == "address" {
+            String::from("address(0)")
+        } else if self.kind == "bool" {
+            String::from("false")
+        } else if self.kind == "string" || self.kind == "bytes" {
+            String::from("''")
+        } else if self.kind.starts_with("bytes") {
+            format!("{}(0)", self.kind)
+        } else {
+            String::from("0")
+        })
+    }
+
+    /// Whether the parameter needs a data location
+    fn is_dynamic(&self) -> bool {
+        self.kind == "string"
+            || self.kind == "bytes"
+            || self.kind.ends_with(']')
+            || self.kind.starts_with("tuple")
+    }
+}
+
+impl AbiFunction {
+    /// Whether a handler should be generated for this entry (non-view external function)
+    pub fn is_mutating(&self) -> bool {
+        self.kind == "function"
+            && self.state_mutability != "view"
+            && self.state_mutability != "pure"
+    }
+
+    /// The ABI types of the parameters (eg "uint256,bytes"), telling the overloads apart
+    pub fn param_types(&self) -> String {
+        self.inputs
+            .iter()
+            .map(|param| param.kind.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// The parameter names used in the handler, unnamed parameters get a positional name. A name
+    /// already taken (eg by "amount" and "_amount") is suffixed with the first free number
+    fn param_names(&self) -> Vec<String> {
+        let mut used = vec![actors::SEED_NAME.to_string()];
+
+        self.inputs
+            .iter()
+            .enumerate()
+            .map(|(i, param)| {
+                let name = if param.name.is_empty() {
+                    format!("_arg{}", i)
+                } else {
+                    format!("_{}", param.name.trim_start_matches('_'))
+                };
+
+                let name = if used.contains(&name) {
+                    (1..)
+                        .map(|n| format!("{}_{}", name, n))
+                        .find(|candidate| !used.contains(candidate))
+                        .unwrap_or_default()
+                } else {
+                    name
+                };
+
+                used.push(name.clone());
+                name
+            })
+            .collect()
+    }
+}
+
+impl Target {
+    /// Load a target from a Foundry artifact (`out/<File>.sol/<Contract>.json`) or a bare ABI json,
+    /// the source of a bare ABI is assumed to be `<src>/<Contract>.sol`
+    pub fn from_file(path: &Path, src: &Path) -> Result<Target> {
+        let content = fs::read_to_string(path)
+            .context(format!("Failed to read ABI file {}", path.display()))?;
+        let json: Value = serde_json::from_str(&content)
+            .context(format!("Failed to parse ABI file {}", path.display()))?;
+
+        let file_stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .ok_or_else(|| anyhow!("Invalid ABI file name {}", path.display()))?;
+
+        let (abi, compilation_target) = match json {
+            Value::Array(_) => (json, None),
+            Value::Object(ref artifact) => (
+                artifact
+                    .get("abi")
+                    .cloned()
+                    .ok_or_else(|| anyhow!("No abi found in {}", path.display()))?,
+                compilation_target(&json),
+            ),
+            _ => return Err(anyhow!("Invalid ABI file {}", path.display())),
+        };
+
+        let entries: Vec<AbiFunction> = serde_json::from_value(abi)
+            .context(format!("Failed to parse ABI entries in {}", path.display()))?;
+
+        let (source, name) = compilation_target
+            .unwrap_or_else(|| (src.join(format!("{}.sol", file_stem)), file_stem));
+
+        let constructor = entries
+            .iter()
+            .find(|entry| entry.kind == "constructor")
+            .map(|entry| entry.inputs.clone())
+            .unwrap_or_default();
+
+        let getters = entries
+            .iter()
+            .filter(|entry| ghosts::is_tracked(entry))
+            .map(|entry| entry.name.clone())
+            .collect();
+
+        Ok(Target {
+            name,
+            source,
+            constructor,
+            functions: entries.into_iter().filter(|f| f.is_mutating()).collect(),
+            getters,
+        })
+    }
+
+    /// Load a target from a `<source>:<Contract>` spec (eg "src/Vault.sol:Vault"), using its
+    /// Foundry artifact in `artifacts` (`<artifacts>/Vault.sol/Vault.json`)
+    pub fn from_spec(spec: &str, artifacts: &Path) -> Result<Target> {
+        let (source, name) = spec
+            .rsplit_once(':')
+            .filter(|(source, name)| !source.is_empty() && !name.is_empty())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Invalid target {}, expected <path>:<Contract> (eg src/Vault.sol:Vault)",
+                    spec
+                )
+            })?;
+
+        let source = PathBuf::from(source);
+        let file_name = source
+            .file_name()
+            .ok_or_else(|| anyhow!("Invalid target source {}", source.display()))?;
+        let artifact = artifacts.join(file_name).join(format!("{}.json", name));
+
+        if !artifact.exists() {
+            return Err(anyhow!(
+                "Artifact {} not found for target {}, did you run forge build ?",
+                artifact.display(),
+                spec
+            ));
+        }
+
+        Ok(Target {
+            name: name.to_string(),
+            source,
+            ..Target::from_file(&artifact, Path::new(""))?
+        })
+    }
+
+    /// The name of the state variable holding this target in Setup (eg "Vault" gives "vault"), a
+    /// reserved word being suffixed (eg "Contract" gives "contract_")
+    pub fn instance_name(&self) -> String {
+        let chars: Vec<char> = self.name.chars().collect();
+        let upper = chars.iter().take_while(|c| !c.is_lowercase()).count();
+
+        // keep the last capital of an acronym followed by a lowercase word (eg "ERC20Token")
+        let lowered = if upper > 1 && upper < chars.len() && chars[upper - 1].is_uppercase() {
+            upper - 1
+        } else {
+            upper.max(1)
+        };
+
+        let name: String = chars
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                if i < lowered {
+                    c.to_ascii_lowercase()
+                } else {
+                    *c
+                }
+            })
+            .collect();
+
+        match validate_identifier(&name) {
+            Ok(()) => name,
+            Err(_) => format!("{}_", name),
+        }
+    }
+
+    /// The target and the user-defined types (contracts, structs, enums) its ABI refers to, which
+    /// are all reachable through the target source file
+    fn symbols(&self) -> Vec<String> {
+        let mut symbols = vec![self.name.clone()];
+
+        let params = self
+            .constructor
+            .iter()
+            .chain(self.functions.iter().flat_map(|function| &function.inputs));
+
+        for param in params {
+            let symbol = param.internal_type.as_deref().and_then(|internal| {
+                ["contract ", "struct ", "enum "]
+                    .iter()
+                    .find_map(|prefix| internal.strip_prefix(prefix))
+            });
+
+            // "Vault.Params[]" is reached through "Vault"
+            if let Some(symbol) = symbol.and_then(|s| s.split(['.', '[']).next()) {
+                if !symbols.iter().any(|known| known == symbol) {
+                    symbols.push(symbol.to_string());
+                }
+            }
+        }
+
+        symbols
+    }
+
+    /// The "import {Vault, IERC20} from '../../../src/Vault.sol';" line, as seen from `from_dir`
+    /// (relative to the project `root`)
+    pub fn import(&self, root: &Path, from_dir: &Path) -> Result<String> {
+        Ok(format!(
+            "import {{{}}} from '{}';\n",
+            self.symbols().join(", "),
+            source_import_path(root, from_dir, &self.source)?
+        ))
+    }
+
+    /// The state variable declaration in Setup
+    pub fn declaration(&self) -> String {
+        format!("  {} internal {};\n", self.name, self.instance_name())
+    }
+
+    /// The deployment of this target in the Setup constructor, with placeholder arguments derived
+    /// from the ABI constructor inputs
+    pub fn deployment(&self) -> String {
+        let mut output = String::new();
+
+        if !self.constructor.is_empty() {
+            let _ = writeln!(
+                output,
+                "    // TODO: set the {} constructor arguments",
+                self.name
+            );
+        }
+
+        // arguments without literal placeholder (structs, fixed arrays) use a zeroed local variable
+        let arguments = self
+            .constructor
+            .iter()
+            .enumerate()
+            .map(|(i, param)| {
+                param.placeholder().unwrap_or_else(|| {
+                    let local = format!("_{}{}", self.instance_name(), i);
+                    let _ = writeln!(output, "    {} {};", param.solidity_type(), local);
+                    local
+                })
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let _ = writeln!(
+            output,
+            "    {} = new {}({});",
+            self.instance_name(),
+            self.name,
+            arguments
+        );
+
+        output
+    }
+
+    /// The name of the handler wrapping `function`, namespaced by target as every handler ends up
+    /// in HandlersParent (eg "handler_vault_deposit")
+    pub fn handler_name(&self, function: &AbiFunction) -> String {
+        format!(
+            "{}{}_{}",
+            HANDLER_PREFIX,
+            self.instance_name(),
+            function.name
+        )
+    }
+
+    /// One wrapper per non-view function, named after `names`, calling into the target stored in
+    /// Setup, between the Ghosts hooks when the target has tracked getters. `holders` keeps the
+    /// address inputs among the accounts summed by the ERC20 property pack
+    pub fn handler_body(&self, actors: bool, holders: bool, names: &[String]) -> String {
+        // the ghost variables track the account calling the target
+        let account = if actors {
+            "currentActor"
+        } else {
+            "address(this)"
+        };
+
+        self.functions
+            .iter()
+            .zip(names)
+            .fold(String::new(), |mut output, (function, handler)| {
+                let names = function.param_names();
+                let mut params = function
+                    .inputs
+                    .iter()
+                    .zip(&names)
+                    .map(|(param, name)| format!("{} {}", param.solidity_type(), name))
+                    .collect::<Vec<_>>();
+
+                // the caller is picked among the actors from an additional fuzzed seed
+                if actors {
+                    params.push(actors::SEED_PARAM.to_string());
+                }
+
+                let payable = function.state_mutability == "payable";
+
+                if !output.is_empty() {
+                    let _ = writeln!(output);
+                }
+
+                let _ = writeln!(
+                    output,
+                    "  function {}({}) public{}{} {{",
+                    handler,
+                    params.join(", "),
+                    if payable { " payable" } else { "" },
+                    if actors {
+                        format!(" {}", actors::MODIFIER)
+                    } else {
+                        String::new()
+                    }
+                );
+
+                // integers are bounded by the Setup MAX_INPUT, signed ones from both sides
+                for (param, name) in function.inputs.iter().zip(&names) {
+                    let bounded = match param.kind.as_str() {
+                        kind if is_sized(kind, "uint") => {
+                            format!("_clamp({}, 0, {})", name, MAX_INPUT)
+                        }
+                        kind if is_sized(kind, "int") => format!(
+                            "_clampInt({}, -int256({}), int256({}))",
+                            name, MAX_INPUT, MAX_INPUT
+                        ),
+                        _ => continue,
+                    };
+
+                    let _ = writeln!(
+                        output,
+                        "    {} = {};",
+                        name,
+                        if param.kind == "uint256" || param.kind == "int256" {
+                            bounded
+                        } else {
+                            format!("{}({})", param.kind, bounded)
+                        }
+                    );
+                }
+
+                // the actors, or this contract without them, hold the tokens sent by the handlers
+                for (param, name) in function.inputs.iter().zip(&names) {
+                    let internal = param.internal_type.as_deref().unwrap_or("address");
+
+                    if !holders || param.kind != "address" || internal.starts_with("contract ") {
+                        continue;
+                    }
+
+                    let holder = if actors {
+                        format!("actors[uint160({}) % actors.length]", name)
+                    } else {
+                        String::from("address(this)")
+                    };
+
+                    let _ = writeln!(output, "    {} = {};", name, holder);
+                }
+
+                let tracked = !self.getters.is_empty();
+
+                if tracked {
+                    let _ = writeln!(
+                        output,
+                        "    {}({});",
+                        ghosts::hook_name(self, ghosts::BEFORE),
+                        account
+                    );
+                }
+
+                let _ = writeln!(
+                    output,
+                    "    {}.{}{}({});",
+                    self.instance_name(),
+                    function.name,
+                    if payable { "{value: msg.value}" } else { "" },
+                    names.join(", ")
+                );
+
+                if tracked {
+                    let _ = writeln!(
+                        output,
+                        "    {}({});",
+                        ghosts::hook_name(self, ghosts::AFTER),
+                        account
+                    );
+                }
+
+                let _ = writeln!(output, "  }}");
+
+                output
+            })
+    }
+}
+
+/// Read the source path and contract name from the artifact metadata, if any
+fn compilation_target(artifact: &Value) -> Option<(PathBuf, String)> {
+    let metadata = match artifact.get("metadata")? {
+        Value::String(raw) => serde_json::from_str(raw).ok()?,
+        metadata => metadata.clone(),
+    };
+
+    let (source, name) = metadata
+        .get("settings")?
+        .get("compilationTarget")?
+        .as_object()?
+        .iter()
+        .next()?;
+
+    Some((PathBuf::from(source), name.as_str()?.to_string()))
+}
+
+/// The import path of a source file from the fuzz suite root, both relative to the project `root`
+pub fn source_import_path(root: &Path, out: &Path, source: &Path) -> Result<String> {
+    let (out, source) = (normalize_path(out), normalize_path(source));
+
+    // the directories left with ".." are only known from the absolute project root
+    let outside = |path: &Path| path.is_absolute() || path.starts_with("..");
+
+    if outside(&out) || outside(&source) {
+        let root = std::path::absolute(root).context(format!(
+            "Failed to resolve the project root {}",
+            root.display()
+        ))?;
+        Ok(relative_import_path(&root.join(out), &root.join(source)))
+    } else {
+        Ok(relative_import_path(&out, &source))
+    }
+}
+
+/// The signature types of the parameter types declared by the handlers of `targets`, when they
+/// differ (eg "address" for "IERC20", "uint8" for "Mode", "(uint256,address)" for "Vault.Params")
+pub fn canonical_types(targets: &[Target]) -> BTreeMap<String, String> {
+    targets
+        .iter()
+        .flat_map(|target| &target.functions)
+        .flat_map(|function| &function.inputs)
+        .map(|param| (param.base_type(), param.canonical_type()))
+        .filter(|(declared, canonical)| declared != canonical)
+        .collect()
+}
+
+/// The Setup constant bounding the integer inputs of the generated handlers
+pub const MAX_INPUT: &str = "MAX_INPUT";
+
+/// Whether an ABI type is an elementary type with a size (eg "uint8" for "uint"), arrays excluded
+fn is_sized(kind: &str, prefix: &str) -> bool {
+    kind.strip_prefix(prefix)
+        .is_some_and(|size| !size.is_empty() && size.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// The input bound and clamping helpers used by the generated handlers, declared in Setup
+pub fn clamp_helper() -> &'static str {
+    "  // upper bound of the fuzzed integer inputs (lower bound of the signed ones, negated), lower it
+  // to the amounts the targets handle
+  uint256 internal constant MAX_INPUT = type(uint128).max;
+
+  function _clamp(uint256 _value, uint256 _min, uint256 _max) internal pure returns (uint256) {
+    if (_min == 0 && _max == type(uint256).max) return _value;
+    if (_max <= _min) return _min;
+    return _min + (_value % (_max - _min + 1));
+  }
+
+  function _clampInt(int256 _value, int256 _min, int256 _max) internal pure returns (int256) {
+    if (_value >= _min && _value <= _max) return _value;
+    if (_max <= _min) return _min;
+    unchecked {
+      return _min + int256((uint256(_value) - uint256(_min)) % (uint256(_max - _min) + 1));
+    }
+  }
+"
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const VAULT_ABI: &str = r#"[
+        {"type": "constructor", "inputs": [{"name": "_asset", "type": "address", "internalType": "contract IERC20"}], "stateMutability": "nonpayable"},
+        {"type": "function", "name": "deposit", "inputs": [{"name": "assets", "type": "uint256", "internalType": "uint256"}, {"name": "receiver", "type": "address", "internalType": "address"}], "outputs": [{"name": "", "type": "uint256", "internalType": "uint256"}], "stateMutability": "nonpayable"},
+        {"type": "function", "name": "totalAssets", "inputs": [], "outputs": [{"name": "", "type": "uint256", "internalType": "uint256"}], "stateMutability": "view"},
+        {"type": "function", "name": "donate", "inputs": [{"name": "", "type": "uint8", "internalType": "uint8"}, {"name": "memo", "type": "string", "internalType": "string"}], "outputs": [], "stateMutability": "payable"},
+        {"type": "event", "name": "Deposit", "inputs": [], "anonymous": false}
+    ]"#;
+
+    #[test]
+    fn test_from_file_bare_abi() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("Vault.json");
+        fs::write(&path, VAULT_ABI)?;
+
+        let target = Target::from_file(&path, Path::new("src"))?;
+
+        assert_eq!(target.name, "Vault");
+        assert_eq!(target.source, PathBuf::from("src/Vault.sol"));
+        assert_eq!(target.constructor.len(), 1);
+        assert_eq!(
+            target
+                .functions
+                .iter()
+                .map(|f| f.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["deposit", "donate"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_file_foundry_artifact() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("Vault.json");
+        fs::write(
+            &path,
+            format!(
+                r#"{{"abi": {}, "metadata": {{"settings": {{"compilationTarget": {{"src/vaults/Vault.sol": "Vault"}}}}}}}}"#,
+                VAULT_ABI
+            ),
+        )?;
+
+        let target = Target::from_file(&path, Path::new("src"))?;
+
+        assert_eq!(target.name, "Vault");
+        assert_eq!(target.source, PathBuf::from("src/vaults/Vault.sol"));
+        assert_eq!(target.functions.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_file_invalid() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("Vault.json");
+        fs::write(&path, r#"{"bytecode": "0x"}"#)?;
+
+        assert!(Target::from_file(&path, Path::new("src")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_spec() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::create_dir_all(temp_dir.path().join("Vault.sol"))?;
+        fs::write(
+            temp_dir.path().join("Vault.sol/Vault.json"),
+            format!(r#"{{"abi": {}}}"#, VAULT_ABI),
+        )?;
+
+        let target = Target::from_spec("src/vaults/Vault.sol:Vault", temp_dir.path())?;
+
+        assert_eq!(target.name, "Vault");
+        assert_eq!(target.source, PathBuf::from("src/vaults/Vault.sol"));
+        assert_eq!(target.constructor.len(), 1);
+        assert_eq!(target.functions.len(), 2);
+
+        assert!(Target::from_spec("src/Vault.sol", temp_dir.path()).is_err());
+        assert!(Target::from_spec("src/Vault.sol:", temp_dir.path()).is_err());
+        assert!(Target::from_spec("src/Oracle.sol:Oracle", temp_dir.path())
+            .unwrap_err()
+            .to_string()
+            .ends_with("did you run forge build ?"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_canonical_type() {
+        let param: AbiParam = serde_json::from_str(
+            r#"{"name": "params", "type": "tuple[]", "internalType": "struct Vault.Params[]", "components": [
+                {"name": "amount", "type": "uint256"},
+                {"name": "route", "type": "tuple", "internalType": "struct Vault.Route", "components": [{"name": "pool", "type": "address", "internalType": "contract IPool"}]}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(param.canonical_type(), "(uint256,(address))[]");
+
+        let target = Target {
+            name: "Vault".to_string(),
+            source: PathBuf::from("src/Vault.sol"),
+            constructor: vec![],
+            getters: vec![],
+            functions: serde_json::from_str(
+                r#"[{"type": "function", "name": "swap", "inputs": [{"name": "mode", "type": "uint8", "internalType": "enum Mode"}, {"name": "token", "type": "address", "internalType": "contract IERC20"}, {"name": "amount", "type": "uint256", "internalType": "uint256"}], "stateMutability": "nonpayable"}]"#,
+            )
+            .unwrap(),
+        };
+
+        assert_eq!(
+            canonical_types(&[target]),
+            BTreeMap::from([
+                ("IERC20".to_string(), "address".to_string()),
+                ("Mode".to_string(), "uint8".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_placeholder() {
+        let param = |kind: &str, internal_type: &str| AbiParam {
+            name: String::new(),
+            kind: kind.to_string(),
+            internal_type: Some(internal_type.to_string()),
+            components: vec![],
+        };
+
+        assert_eq!(
+            param("address", "address").placeholder().unwrap(),
+            "address(0)"
+        );
+        assert_eq!(
+            param("address", "address payable").placeholder().unwrap(),
+            "payable(address(0))"
+        );
+        assert_eq!(
+            param("address", "contract IERC20").placeholder().unwrap(),
+            "IERC20(address(0))"
+        );
+        assert_eq!(
+            param("uint8", "enum Vault.Mode").placeholder().unwrap(),
+            "Vault.Mode(0)"
+        );
+        assert_eq!(param("int24", "int24").placeholder().unwrap(), "0");
+        assert_eq!(param("bool", "bool").placeholder().unwrap(), "false");
+        assert_eq!(
+            param("bytes32", "bytes32").placeholder().unwrap(),
+            "bytes32(0)"
+        );
+        assert_eq!(param("string", "string").placeholder().unwrap(), "''");
+        assert_eq!(
+            param("address[]", "address[]").placeholder().unwrap(),
+            "new address[](0)"
+        );
+        assert_eq!(
+            param("string[]", "string[]").placeholder().unwrap(),
+            "new string[](0)"
+        );
+        assert_eq!(
+            param("bytes[]", "bytes[]").placeholder().unwrap(),
+            "new bytes[](0)"
+        );
+        assert_eq!(
+            param("uint256[][]", "uint256[][]").placeholder().unwrap(),
+            "new uint256[][](0)"
+        );
+        assert_eq!(
+            param("address[]", "contract IERC20[]")
+                .placeholder()
+                .unwrap(),
+            "new IERC20[](0)"
+        );
+        assert_eq!(param("uint256[2]", "uint256[2]").placeholder(), None);
+        assert_eq!(param("tuple", "struct Vault.Params").placeholder(), None);
+    }
+
+    #[test]
+    fn test_deployment() {
+        let target = Target {
+            name: "Vault".to_string(),
+            source: PathBuf::from("src/Vault.sol"),
+            constructor: serde_json::from_str(
+                r#"[{"name": "_asset", "type": "address", "internalType": "contract IERC20"}, {"name": "_params", "type": "tuple", "internalType": "struct Vault.Params"}]"#,
+            )
+            .unwrap(),
+            getters: vec![],
+            functions: vec![],
+        };
+
+        assert_eq!(
+            target.deployment(),
+            "    // TODO: set the Vault constructor arguments
+    Vault.Params memory _vault1;
+    vault = new Vault(IERC20(address(0)), _vault1);
+"
+        );
+
+        // the dynamic arrays are allocated without data location
+        let target = Target {
+            constructor: serde_json::from_str(
+                r#"[{"name": "_names", "type": "string[]", "internalType": "string[]"}, {"name": "_data", "type": "bytes[]", "internalType": "bytes[]"}]"#,
+            )
+            .unwrap(),
+            ..target
+        };
+        assert_eq!(
+            target.deployment(),
+            "    // TODO: set the Vault constructor arguments\n    vault = new Vault(new string[](0), new bytes[](0));\n"
+        );
+
+        let target = Target {
+            constructor: vec![],
+            ..target
+        };
+        assert_eq!(target.deployment(), "    vault = new Vault();\n");
+    }
+
+    #[test]
+    fn test_instance_name() {
+        let target = |name: &str| Target {
+            name: name.to_string(),
+            source: PathBuf::new(),
+            constructor: vec![],
+            getters: vec![],
+            functions: vec![],
+        };
+
+        assert_eq!(target("Vault").instance_name(), "vault");
+        assert_eq!(target("ERC20Token").instance_name(), "erc20Token");
+        assert_eq!(target("USDC").instance_name(), "usdc");
+        assert_eq!(target("wETH").instance_name(), "wETH");
+
+        // the lowered name of a contract can be a reserved word
+        assert_eq!(target("Contract").instance_name(), "contract_");
+        assert_eq!(target("Error").instance_name(), "error_");
+        assert_eq!(target("Event").instance_name(), "event_");
+        assert_eq!(target("UINT8").instance_name(), "uint8_");
+    }
+
+    #[test]
+    fn test_import_symbols() {
+        let functions: Vec<AbiFunction> = serde_json::from_str(
+            r#"[{"type": "function", "name": "swap", "inputs": [{"name": "params", "type": "tuple[]", "internalType": "struct Vault.Params[]"}, {"name": "mode", "type": "uint8", "internalType": "enum Mode"}, {"name": "token", "type": "address", "internalType": "contract IERC20"}], "stateMutability": "nonpayable"}]"#,
+        )
+        .unwrap();
+
+        let target = Target {
+            name: "Vault".to_string(),
+            source: PathBuf::from("src/Vault.sol"),
+            constructor: serde_json::from_str(
+                r#"[{"name": "_asset", "type": "address", "internalType": "contract IERC20"}]"#,
+            )
+            .unwrap(),
+            functions,
+            getters: vec![],
+        };
+
+        assert_eq!(
+            target
+                .import(Path::new("."), Path::new("test/fuzz/handlers"))
+                .unwrap(),
+            "import {Vault, IERC20, Mode} from '../../../src/Vault.sol';\n"
+        );
+    }
+
+    #[test]
+    fn test_import() {
+        let target = Target {
+            name: "Vault".to_string(),
+            source: PathBuf::from("src/Vault.sol"),
+            constructor: vec![],
+            getters: vec![],
+            functions: vec![],
+        };
+
+        assert_eq!(
+            target
+                .import(Path::new("."), Path::new("./test/invariants/fuzz"))
+                .unwrap(),
+            "import {Vault} from '../../../src/Vault.sol';\n"
+        );
+    }
+
+    #[test]
+    fn test_source_import_path() {
+        let root = Path::new("/projects/vault");
+        let source = Path::new("src/Vault.sol");
+
+        assert_eq!(
+            source_import_path(root, Path::new("test/fuzz/../fuzz"), source).unwrap(),
+            "../../src/Vault.sol"
+        );
+
+        // a suite out of the project root goes through the root directory name
+        assert_eq!(
+            source_import_path(root, Path::new("../x"), source).unwrap(),
+            "../vault/src/Vault.sol"
+        );
+        assert_eq!(
+            source_import_path(root, Path::new("../x/handlers"), source).unwrap(),
+            "../../vault/src/Vault.sol"
+        );
+        assert_eq!(
+            source_import_path(root, Path::new("/projects/fuzz"), source).unwrap(),
+            "../vault/src/Vault.sol"
+        );
+    }
+
+    #[test]
+    fn test_param_names() {
+        let function: AbiFunction = serde_json::from_str(
+            r#"{"type": "function", "name": "f", "inputs": [
+                {"name": "amount", "type": "uint256"},
+                {"name": "_amount", "type": "uint256"},
+                {"name": "", "type": "address"},
+                {"name": "arg2", "type": "address"},
+                {"name": "actorSeed", "type": "uint256"}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            function.param_names(),
+            vec!["_amount", "_amount_1", "_arg2", "_arg2_1", "_actorSeed_1"]
+        );
+    }
+
+    #[test]
+    fn test_handler_body() {
+        let functions: Vec<AbiFunction> = serde_json::from_str(VAULT_ABI).unwrap();
+        let target = Target {
+            name: "Vault".to_string(),
+            source: PathBuf::from("src/Vault.sol"),
+            constructor: vec![],
+            getters: vec![],
+            functions: functions.into_iter().filter(|f| f.is_mutating()).collect(),
+        };
+        let names: Vec<String> = target
+            .functions
+            .iter()
+            .map(|function| target.handler_name(function))
+            .collect();
+
+        assert_eq!(
+            target.handler_body(false, false, &names),
+            "  function handler_vault_deposit(uint256 _assets, address _receiver) public {
+    _assets = _clamp(_assets, 0, MAX_INPUT);
+    vault.deposit(_assets, _receiver);
+  }
+
+  function handler_vault_donate(uint8 _arg0, string memory _memo) public payable {
+    _arg0 = uint8(_clamp(_arg0, 0, MAX_INPUT));
+    vault.donate{value: msg.value}(_arg0, _memo);
+  }
+"
+        );
+    }
+
+    #[test]
+    fn test_handler_body_bounds() {
+        let functions: Vec<AbiFunction> = serde_json::from_str(
+            r#"[{"type": "function", "name": "rebalance", "inputs": [{"name": "delta", "type": "int256"}, {"name": "tick", "type": "int24"}, {"name": "amounts", "type": "uint256[]"}], "stateMutability": "nonpayable"}]"#,
+        )
+        .unwrap();
+        let target = Target {
+            name: "Pool".to_string(),
+            source: PathBuf::from("src/Pool.sol"),
+            constructor: vec![],
+            getters: vec![],
+            functions,
+        };
+
+        // signed integers are bounded from both sides, arrays aren't bounded
+        assert_eq!(
+            target.handler_body(false, false, &[String::from("handler_pool_rebalance")]),
+            "  function handler_pool_rebalance(int256 _delta, int24 _tick, uint256[] memory _amounts) public {
+    _delta = _clampInt(_delta, -int256(MAX_INPUT), int256(MAX_INPUT));
+    _tick = int24(_clampInt(_tick, -int256(MAX_INPUT), int256(MAX_INPUT)));
+    pool.rebalance(_delta, _tick, _amounts);
+  }
+"
+        );
+    }
+
+    #[test]
+    fn test_handler_body_holders() {
+        let functions: Vec<AbiFunction> = serde_json::from_str(
+            r#"[{"type": "function", "name": "transferFrom", "inputs": [{"name": "from", "type": "address", "internalType": "address"}, {"name": "to", "type": "address", "internalType": "address payable"}, {"name": "amount", "type": "uint256"}], "stateMutability": "nonpayable"}, {"type": "function", "name": "approve", "inputs": [{"name": "spender", "type": "address", "internalType": "contract IVault"}], "stateMutability": "nonpayable"}]"#,
+        )
+        .unwrap();
+        let target = Target {
+            name: "Token".to_string(),
+            source: PathBuf::from("src/Token.sol"),
+            constructor: vec![],
+            getters: vec![],
+            functions,
+        };
+        let names: Vec<String> = target
+            .functions
+            .iter()
+            .map(|function| target.handler_name(function))
+            .collect();
+
+        // contract inputs are left to the fuzzer
+        assert_eq!(
+            target.handler_body(false, true, &names),
+            "  function handler_token_transferFrom(address _from, address _to, uint256 _amount) public {
+    _amount = _clamp(_amount, 0, MAX_INPUT);
+    _from = address(this);
+    _to = address(this);
+    token.transferFrom(_from, _to, _amount);
+  }
+
+  function handler_token_approve(IVault _spender) public {
+    token.approve(_spender);
+  }
+"
+        );
+
+        assert!(target
+            .handler_body(true, true, &names)
+            .contains("    _from = actors[uint160(_from) % actors.length];\n    _to = actors[uint160(_to) % actors.length];\n"));
+    }
+
+    #[test]
+    fn test_handler_body_actors() {
+        let functions: Vec<AbiFunction> = serde_json::from_str(VAULT_ABI).unwrap();
+        let target = Target {
+            name: "Vault".to_string(),
+            source: PathBuf::from("src/Vault.sol"),
+            constructor: vec![],
+            getters: vec![],
+            functions: functions
+                .into_iter()
+                .filter(|f| f.is_mutating())
+                .take(1)
+                .collect(),
+        };
+        let names: Vec<String> = target
+            .functions
+            .iter()
+            .map(|function| target.handler_name(function))
+            .collect();
+
+        assert_eq!(
+            target.handler_body(true, false, &names),
+            "  function handler_vault_deposit(uint256 _assets, address _receiver, uint256 _actorSeed) public useActor(_actorSeed) {
+    _assets = _clamp(_assets, 0, MAX_INPUT);
+    vault.deposit(_assets, _receiver);
+  }
+"
+        );
+    }
+}
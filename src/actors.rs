@@ -0,0 +1,62 @@
+use crate::cheatcodes;
+
+/// The seed parameter added to every target handler function when actors are enabled
+pub const SEED_PARAM: &str = "uint256 _actorSeed";
+
+/// The name of the seed parameter, which the ABI parameters can't take
+pub const SEED_NAME: &str = "_actorSeed";
+
+/// The modifier pranking the actor picked from the seed, on every target handler function
+pub const MODIFIER: &str = "useActor(_actorSeed)";
+
+/// The actors state variable, declared in Setup
+pub fn declaration() -> &'static str {
+    "  address[] internal actors;\n"
+}
+
+/// The Setup constructor lines creating `count` actors, at the Medusa default sender addresses
+/// (0x10000, 0x20000, 0x30000, ...)
+pub fn deployment(count: u8) -> String {
+    format!(
+        "    for (uint256 _i; _i < {}; _i++) {{
+      actors.push(address(uint160(0x10000 * (_i + 1))));
+    }}
+",
+        count
+    )
+}
+
+/// The Actors contract body: the cheatcodes instance and the modifier pranking a fuzzed actor
+pub fn body() -> String {
+    format!(
+        "{}
+  address internal currentActor;
+
+  modifier useActor(uint256 _actorSeed) {{
+    currentActor = actors[_actorSeed % actors.length];
+    vm.startPrank(currentActor);
+    _;
+    vm.stopPrank();
+  }}
+",
+        cheatcodes::declaration()
+    )
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deployment() {
+        assert_eq!(
+            deployment(3),
+            "    for (uint256 _i; _i < 3; _i++) {
+      actors.push(address(uint160(0x10000 * (_i + 1))));
+    }
+"
+        );
+    }
+}
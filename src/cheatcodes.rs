@@ -0,0 +1,29 @@
+/// The cheatcodes contract address, supported by both Medusa and Foundry
+const CHEATCODES_ADDRESS: &str = "0x7109709ECfa91a80626fF3989D68f67F5b1DD12D";
+
+/// The minimal cheatcodes interface used by the generated contracts, rendered with their imports
+pub fn interface() -> &'static str {
+    "
+interface IVm {
+  function warp(uint256 _timestamp) external;
+
+  function roll(uint256 _blockNumber) external;
+
+  function deal(address _account, uint256 _balance) external;
+
+  function prank(address _sender) external;
+
+  function startPrank(address _sender) external;
+
+  function stopPrank() external;
+}
+"
+}
+
+/// The cheatcodes instance, a single contract of the hierarchy declares it
+pub fn declaration() -> String {
+    format!(
+        "  IVm internal constant vm = IVm({});\n",
+        CHEATCODES_ADDRESS
+    )
+}
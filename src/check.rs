@@ -0,0 +1,797 @@
+use crate::types::ContractType;
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A problem found in an existing suite
+#[derive(Debug, Clone, PartialEq)]
+pub enum IssueKind {
+    /// A contract of the suite the entry point doesn't inherit, not even indirectly
+    NotInherited { contract: String, parent: String },
+    /// A contract inheriting a contract which is neither declared nor imported in its file
+    NotImported { contract: String, parent: String },
+    /// A relative import of a file which doesn't exist
+    WrongImportPath { import: String },
+    /// A name imported from a file which doesn't declare it
+    UnknownImport { import: String, name: String },
+    /// A pragma different from the one of the rest of the suite
+    PragmaMismatch { pragma: String, expected: String },
+    /// A function declared by several contracts inherited together, none overriding it
+    DuplicateFunction {
+        function: String,
+        contracts: Vec<String>,
+    },
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IssueKind::NotInherited { contract, parent } => {
+                write!(f, "{} is not inherited by {}", contract, parent)
+            }
+            IssueKind::NotImported { contract, parent } => {
+                write!(f, "{} inherits {} without importing it", contract, parent)
+            }
+            IssueKind::WrongImportPath { import } => write!(f, "{} not found", import),
+            IssueKind::UnknownImport { import, name } => {
+                write!(f, "{} is not declared in {}", name, import)
+            }
+            IssueKind::PragmaMismatch { pragma, expected } => write!(
+                f,
+                "pragma solidity {}, the rest of the suite uses {}",
+                pragma, expected
+            ),
+            IssueKind::DuplicateFunction {
+                function,
+                contracts,
+            } => write!(
+                f,
+                "{} is declared in {}, the linearization requires an override",
+                function,
+                contracts.join(" and ")
+            ),
+        }
+    }
+}
+
+/// An issue and the file it was found in, relative to the fuzz test folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub path: PathBuf,
+    pub kind: IssueKind,
+}
+
+/// A parsed Solidity source, only what is needed to rebuild the inheritance tree
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Source {
+    pragma: Option<String>,
+    imports: Vec<Import>,
+    contracts: Vec<Declaration>,
+}
+
+/// An import statement: the imported path and the imported (name, local name), none for a whole
+/// file import
+#[derive(Debug, Clone, PartialEq)]
+struct Import {
+    path: String,
+    names: Vec<(String, String)>,
+}
+
+/// A contract, interface or library declaration
+#[derive(Debug, Clone, PartialEq)]
+struct Declaration {
+    name: String,
+    /// Interfaces and libraries aren't part of the inheritance tree
+    contract: bool,
+    /// Abstract contracts can't be deployed
+    is_abstract: bool,
+    parents: Vec<String>,
+    functions: Vec<Function>,
+}
+
+/// A function declaration, by signature (eg "handler_deposit(uint256,uint256)")
+#[derive(Debug, Clone, PartialEq)]
+struct Function {
+    name: String,
+    signature: String,
+    overrides: bool,
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// The identifier at the start of `source`, after the whitespaces
+fn identifier(source: &str) -> &str {
+    let source = source.trim_start();
+    &source[..source.find(|c| !is_identifier(c)).unwrap_or(source.len())]
+}
+
+/// The positions of `keyword` in `source`, as a whole word
+fn keyword_positions<'a>(source: &'a str, keyword: &'a str) -> impl Iterator<Item = usize> + 'a {
+    source.match_indices(keyword).map(|(i, _)| i).filter(|&i| {
+        !source[..i].chars().next_back().is_some_and(is_identifier)
+            && !source[i + keyword.len()..]
+                .chars()
+                .next()
+                .is_some_and(is_identifier)
+    })
+}
+
+/// The position of the delimiter closing the one at `open`
+fn closing(source: &str, open: usize, (opening, closing): (char, char)) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, c) in source[open..].char_indices() {
+        if c == opening {
+            depth += 1;
+        } else if c == closing {
+            depth -= 1;
+
+            if depth == 0 {
+                return Some(open + i);
+            }
+        }
+    }
+
+    None
+}
+
+/// Split a list on the commas which aren't nested in parentheses or brackets
+fn split_list(list: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in list.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(list[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    items.push(list[start..].trim());
+    items.retain(|item| !item.is_empty());
+
+    items
+}
+
+/// Replace the comments with a space, the string literals are kept as is
+fn strip_comments(source: &str) -> String {
+    let mut output = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut quote = None;
+
+    while let Some(c) = chars.next() {
+        if let Some(delimiter) = quote {
+            output.push(c);
+
+            if c == '\\' {
+                output.extend(chars.next());
+            } else if c == delimiter {
+                quote = None;
+            }
+
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                while chars.next_if(|&next| next != '\n').is_some() {}
+                output.push(' ');
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+                output.push(' ');
+            }
+            ('"' | '\'', _) => {
+                quote = Some(c);
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+    }
+
+    output
+}
+
+fn parse_import(statement: &str) -> Option<Import> {
+    let start = statement.find(['\'', '"'])?;
+    let delimiter = statement[start..].chars().next()?;
+    let end = start + 1 + statement[start + 1..].find(delimiter)?;
+
+    let names = match (statement.find('{'), statement.find('}')) {
+        (Some(open), Some(close)) if open < close => split_list(&statement[open + 1..close])
+            .into_iter()
+            .filter_map(|name| {
+                let mut words = name.split_whitespace();
+                let name = words.next()?;
+                let local = match (words.next(), words.next()) {
+                    (Some("as"), Some(local)) => local,
+                    _ => name,
+                };
+
+                Some((name.to_string(), local.to_string()))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Some(Import {
+        path: statement[start + 1..end].to_string(),
+        names,
+    })
+}
+
+/// The functions declared in a contract body
+fn parse_functions(body: &str) -> Vec<Function> {
+    keyword_positions(body, "function")
+        .filter_map(|i| {
+            let start = i + "function".len();
+            let name = identifier(&body[start..]);
+
+            // function types have no name
+            if name.is_empty() {
+                return None;
+            }
+
+            let open = start + body[start..].find('(')?;
+            let close = closing(body, open, ('(', ')'))?;
+            let end = body[close..]
+                .find(['{', ';'])
+                .map_or(body.len(), |n| close + n);
+
+            let types: Vec<&str> = split_list(&body[open + 1..close])
+                .into_iter()
+                .filter_map(|parameter| parameter.split_whitespace().next())
+                .collect();
+
+            Some(Function {
+                name: name.to_string(),
+                signature: format!("{}({})", name, types.join(",")),
+                overrides: keyword_positions(&body[close..end], "override")
+                    .next()
+                    .is_some(),
+            })
+        })
+        .collect()
+}
+
+fn parse(source: &str) -> Source {
+    let source = strip_comments(source);
+
+    let statement = |i: usize, keyword: &str| {
+        let statement = &source[i + keyword.len()..];
+        statement.find(';').map(|end| &statement[..end])
+    };
+
+    let pragma = keyword_positions(&source, "pragma").find_map(|i| {
+        let pragma = statement(i, "pragma")?.trim().strip_prefix("solidity")?;
+        Some(pragma.trim().to_string())
+    });
+
+    let imports = keyword_positions(&source, "import")
+        .filter_map(|i| parse_import(statement(i, "import")?))
+        .collect();
+
+    let mut contracts = Vec::new();
+
+    for keyword in ["contract", "interface", "library"] {
+        for i in keyword_positions(&source, keyword) {
+            let Some(open) = source[i..].find('{').map(|n| i + n) else {
+                continue;
+            };
+
+            let header = source[i + keyword.len()..open].trim_start();
+            let name = identifier(header);
+
+            if name.is_empty() {
+                continue;
+            }
+
+            let parents = header[name.len()..]
+                .trim()
+                .strip_prefix("is")
+                .map(|list| {
+                    split_list(list)
+                        .into_iter()
+                        .map(|parent| identifier(parent).to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            let end = closing(&source, open, ('{', '}')).unwrap_or(source.len());
+
+            contracts.push((
+                i,
+                Declaration {
+                    name: name.to_string(),
+                    contract: keyword == "contract",
+                    is_abstract: source[..i].trim_end().ends_with("abstract"),
+                    parents,
+                    functions: parse_functions(&source[open..end]),
+                },
+            ));
+        }
+    }
+
+    contracts.sort_by_key(|(i, _)| *i);
+
+    Source {
+        pragma,
+        imports,
+        contracts: contracts
+            .into_iter()
+            .map(|(_, declaration)| declaration)
+            .collect(),
+    }
+}
+
+/// The names of the contracts of a source which can be deployed, ie neither abstract contracts,
+/// interfaces nor libraries
+pub(crate) fn deployable_contracts(source: &str) -> Vec<String> {
+    parse(source)
+        .contracts
+        .into_iter()
+        .filter(|contract| contract.contract && !contract.is_abstract)
+        .map(|contract| contract.name)
+        .collect()
+}
+
+/// The (contract, function) names of the functions declared by the contracts of a source,
+/// interfaces and libraries left aside
+pub(crate) fn contract_functions(source: &str) -> Vec<(String, String)> {
+    parse(source)
+        .contracts
+        .into_iter()
+        .filter(|contract| contract.contract)
+        .flat_map(|contract| {
+            contract
+                .functions
+                .into_iter()
+                .map(move |function| (contract.name.clone(), function.name))
+        })
+        .collect()
+}
+
+/// The signatures of the functions declared by the contracts of a source, with the parameter
+/// types as written (eg "handler_deposit(uint256,Vault.Params)")
+pub(crate) fn function_signatures(source: &str) -> Vec<String> {
+    parse(source)
+        .contracts
+        .into_iter()
+        .filter(|contract| contract.contract)
+        .flat_map(|contract| contract.functions)
+        .map(|function| function.signature)
+        .collect()
+}
+
+/// Parse the test contracts (".t.sol") of `dir`, recursively, by path relative to `out`
+fn collect(out: &Path, dir: &Path, sources: &mut BTreeMap<PathBuf, Source>) -> Result<()> {
+    let path = out.join(dir);
+
+    for entry in fs::read_dir(&path).context(format!("Failed to read {}", path.display()))? {
+        let relative = dir.join(entry?.file_name());
+        let path = out.join(&relative);
+
+        if path.is_dir() {
+            collect(out, &relative, sources)?;
+        } else if relative.to_string_lossy().ends_with(".t.sol") {
+            let source =
+                fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?;
+            sources.insert(relative, parse(&source));
+        }
+    }
+
+    Ok(())
+}
+
+/// A contract and every contract of the suite it inherits, directly or not
+fn ancestors<'a>(
+    contracts: &BTreeMap<&'a str, (&'a Path, &'a Declaration)>,
+    name: &'a str,
+) -> BTreeSet<&'a str> {
+    let mut ancestors = BTreeSet::new();
+    let mut stack = vec![name];
+
+    while let Some(name) = stack.pop() {
+        if ancestors.insert(name) {
+            if let Some((_, contract)) = contracts.get(name) {
+                stack.extend(contract.parents.iter().map(String::as_str));
+            }
+        }
+    }
+
+    ancestors
+}
+
+/// Check the suite in `out`: every contract is inherited by the entry point (the handlers through
+/// HandlersParent, the properties through PropertiesParent), imports what it inherits from a
+/// valid relative path, uses the same pragma, and no function is declared twice in a
+/// linearization
+pub fn check_suite(out: &Path) -> Result<Vec<Issue>> {
+    let mut sources = BTreeMap::new();
+    collect(out, Path::new(""), &mut sources)?;
+
+    let mut issues = Vec::new();
+
+    for (path, source) in &sources {
+        let dir = out.join(path.parent().unwrap_or(Path::new("")));
+
+        // the names usable in the file, unknown if a whole file can't be resolved
+        let mut available: BTreeSet<String> = source
+            .contracts
+            .iter()
+            .map(|contract| contract.name.clone())
+            .collect();
+        let mut unresolved = false;
+
+        for import in &source.imports {
+            available.extend(import.names.iter().map(|(_, local)| local.clone()));
+
+            // remapped imports (eg forge-std/Test.sol) aren't resolved
+            if !import.path.starts_with('.') {
+                unresolved |= import.names.is_empty();
+                continue;
+            }
+
+            let imported = dir.join(&import.path);
+
+            if !imported.is_file() {
+                issues.push(Issue {
+                    path: path.clone(),
+                    kind: IssueKind::WrongImportPath {
+                        import: import.path.clone(),
+                    },
+                });
+                unresolved |= import.names.is_empty();
+                continue;
+            }
+
+            let imported = parse(
+                &fs::read_to_string(&imported)
+                    .context(format!("Failed to read {}", imported.display()))?,
+            );
+
+            // a file exports what it declares and what it imports
+            let exported: BTreeSet<&str> = imported
+                .contracts
+                .iter()
+                .map(|contract| contract.name.as_str())
+                .chain(
+                    imported
+                        .imports
+                        .iter()
+                        .flat_map(|import| import.names.iter().map(|(_, local)| local.as_str())),
+                )
+                .collect();
+
+            for (name, _) in &import.names {
+                if !exported.contains(name.as_str()) {
+                    issues.push(Issue {
+                        path: path.clone(),
+                        kind: IssueKind::UnknownImport {
+                            import: import.path.clone(),
+                            name: name.clone(),
+                        },
+                    });
+                }
+            }
+
+            if import.names.is_empty() {
+                available.extend(exported.into_iter().map(String::from));
+            }
+        }
+
+        for contract in &source.contracts {
+            for parent in &contract.parents {
+                if !unresolved && !available.contains(parent) {
+                    issues.push(Issue {
+                        path: path.clone(),
+                        kind: IssueKind::NotImported {
+                            contract: contract.name.clone(),
+                            parent: parent.clone(),
+                        },
+                    });
+                }
+            }
+        }
+    }
+
+    // the pragma of most files is the expected one
+    let mut pragmas: BTreeMap<&str, usize> = BTreeMap::new();
+    for pragma in sources
+        .values()
+        .filter_map(|source| source.pragma.as_deref())
+    {
+        *pragmas.entry(pragma).or_default() += 1;
+    }
+
+    if let Some((expected, _)) = pragmas.iter().rev().max_by_key(|(_, count)| **count) {
+        for (path, source) in &sources {
+            if let Some(pragma) = source.pragma.as_deref().filter(|pragma| pragma != expected) {
+                issues.push(Issue {
+                    path: path.clone(),
+                    kind: IssueKind::PragmaMismatch {
+                        pragma: pragma.to_string(),
+                        expected: expected.to_string(),
+                    },
+                });
+            }
+        }
+    }
+
+    let contracts: BTreeMap<&str, (&Path, &Declaration)> = sources
+        .iter()
+        .flat_map(|(path, source)| {
+            source
+                .contracts
+                .iter()
+                .filter(|contract| contract.contract)
+                .map(move |contract| (contract.name.as_str(), (path.as_path(), contract)))
+        })
+        .collect();
+
+    // the Foundry entry point inherits PropertiesParent too, the reproducer inherits FuzzTest
+    let entry_point = ContractType::EntryPoint.name();
+    let entry_points = [entry_point, ContractType::FoundryEntryPoint.name()];
+    let tree: BTreeSet<&str> = entry_points
+        .iter()
+        .flat_map(|entry_point| ancestors(&contracts, entry_point))
+        .collect();
+
+    for (name, (path, _)) in &contracts {
+        if tree.contains(name)
+            || ancestors(&contracts, name)
+                .iter()
+                .any(|ancestor| entry_points.contains(ancestor))
+        {
+            continue;
+        }
+
+        // handlers and properties are expected to be inherited by their parent, if the parent
+        // inherits them it is the one missing from the tree
+        let parent = [ContractType::Handler, ContractType::Property]
+            .iter()
+            .find(|contract_type| path.starts_with(contract_type.directory_name()))
+            .map(|contract_type| contract_type.inherited_name())
+            .filter(|parent| parent != name);
+
+        if let Some(parent) = parent {
+            if contracts
+                .get(parent)
+                .is_some_and(|(_, parent)| parent.parents.iter().any(|child| child == name))
+            {
+                continue;
+            }
+        }
+
+        issues.push(Issue {
+            path: path.to_path_buf(),
+            kind: IssueKind::NotInherited {
+                contract: name.to_string(),
+                parent: parent.unwrap_or(entry_point).to_string(),
+            },
+        });
+    }
+
+    // every linearization is the one of a contract no other contract inherits
+    let inherited: BTreeSet<&str> = contracts
+        .values()
+        .flat_map(|(_, contract)| contract.parents.iter().map(String::as_str))
+        .collect();
+    let mut duplicates = BTreeSet::new();
+
+    for name in contracts.keys().filter(|name| !inherited.contains(*name)) {
+        let mut declarations: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        let mut overridden = BTreeSet::new();
+
+        for ancestor in ancestors(&contracts, name) {
+            let Some((_, contract)) = contracts.get(ancestor) else {
+                continue;
+            };
+
+            for function in &contract.functions {
+                if function.overrides {
+                    overridden.insert(function.signature.as_str());
+                } else {
+                    declarations
+                        .entry(function.signature.as_str())
+                        .or_default()
+                        .push(ancestor);
+                }
+            }
+        }
+
+        for (function, declared_in) in declarations {
+            if declared_in.len() > 1
+                && !overridden.contains(function)
+                && duplicates.insert((function, declared_in.clone()))
+            {
+                issues.push(Issue {
+                    path: contracts[declared_in[0]].0.to_path_buf(),
+                    kind: IssueKind::DuplicateFunction {
+                        function: function.to_string(),
+                        contracts: declared_in.iter().map(|name| name.to_string()).collect(),
+                    },
+                });
+            }
+        }
+    }
+
+    issues.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(issues)
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::DiskSink;
+    use crate::suite::SuiteBuilder;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse() {
+        let source = parse(
+            "// SPDX-License-Identifier: MIT
+pragma solidity 0.8.23;
+
+import {Setup} from './Setup.t.sol';
+import { HandlersA as A, HandlersB } from \"./HandlersB.t.sol\";
+import 'forge-std/Test.sol';
+
+/* contract Commented is A {} */
+interface IHevm {
+  function warp(uint256) external;
+}
+
+contract HandlersParent is A, HandlersB, Setup(1, 2) {
+  // function commented() public {}
+  function handler_deposit(uint256 _assets, address[] memory _to) public {}
+
+  function _clamp(uint256 _value) internal override(Setup) returns (uint256) {}
+}",
+        );
+
+        assert_eq!(source.pragma.as_deref(), Some("0.8.23"));
+        assert_eq!(
+            source.imports[1],
+            Import {
+                path: String::from("./HandlersB.t.sol"),
+                names: vec![
+                    (String::from("HandlersA"), String::from("A")),
+                    (String::from("HandlersB"), String::from("HandlersB"))
+                ]
+            }
+        );
+        assert!(source.imports[2].names.is_empty());
+
+        assert_eq!(source.contracts.len(), 2);
+        assert!(!source.contracts[0].contract);
+        assert_eq!(
+            deployable_contracts("abstract contract Base {}\ncontract Vault is Base {}"),
+            vec![String::from("Vault")]
+        );
+
+        let contract = &source.contracts[1];
+        assert_eq!(contract.name, "HandlersParent");
+        assert_eq!(contract.parents, vec!["A", "HandlersB", "Setup"]);
+        assert_eq!(
+            contract.functions,
+            vec![
+                Function {
+                    name: String::from("handler_deposit"),
+                    signature: String::from("handler_deposit(uint256,address[])"),
+                    overrides: false
+                },
+                Function {
+                    name: String::from("_clamp"),
+                    signature: String::from("_clamp(uint256)"),
+                    overrides: true
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_suite() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let out = temp_dir.path().join("test/fuzz");
+
+        let spec = SuiteBuilder::new()
+            .with_out(Path::new("test/fuzz"))
+            .with_handler("A")
+            .with_handler("B")
+            .with_property("A")
+            .with_actors(2)
+            .with_foundry(true)
+            .build();
+        crate::generate_suite(&spec)?.write(&mut DiskSink::new(temp_dir.path()), false)?;
+
+        assert_eq!(check_suite(&out)?, vec![]);
+
+        // a handler left out of HandlersParent, with a duplicate function
+        fs::write(
+            out.join("handlers/HandlersC.t.sol"),
+            "pragma solidity 0.8.23;\nimport {Setup} from './Setup.t.sol';\n\ncontract HandlersC is Setup {\n  function handler_a() public {}\n}",
+        )?;
+        fs::write(
+            out.join("handlers/HandlersB.t.sol"),
+            fs::read_to_string(out.join("handlers/HandlersB.t.sol"))?
+                .replace("pragma solidity 0.8.23", "pragma solidity 0.8.20")
+                .replace("Actors {\n", "Actors {\n  function handler_a() public {}\n"),
+        )?;
+        fs::write(
+            out.join("handlers/HandlersA.t.sol"),
+            fs::read_to_string(out.join("handlers/HandlersA.t.sol"))?
+                .replace("Actors {\n", "Actors {\n  function handler_a() public {}\n"),
+        )?;
+        fs::write(
+            out.join("FuzzTest.t.sol"),
+            fs::read_to_string(out.join("FuzzTest.t.sol"))?.replace(
+                "import {PropertiesParent} from './properties/PropertiesParent.t.sol';\n",
+                "",
+            ),
+        )?;
+
+        assert_eq!(
+            check_suite(&out)?,
+            vec![
+                Issue {
+                    path: PathBuf::from("FuzzTest.t.sol"),
+                    kind: IssueKind::NotImported {
+                        contract: String::from("FuzzTest"),
+                        parent: String::from("PropertiesParent")
+                    }
+                },
+                Issue {
+                    path: PathBuf::from("handlers/HandlersA.t.sol"),
+                    kind: IssueKind::DuplicateFunction {
+                        function: String::from("handler_a()"),
+                        contracts: vec![String::from("HandlersA"), String::from("HandlersB")]
+                    }
+                },
+                Issue {
+                    path: PathBuf::from("handlers/HandlersB.t.sol"),
+                    kind: IssueKind::PragmaMismatch {
+                        pragma: String::from("0.8.20"),
+                        expected: String::from("0.8.23")
+                    }
+                },
+                Issue {
+                    path: PathBuf::from("handlers/HandlersC.t.sol"),
+                    kind: IssueKind::WrongImportPath {
+                        import: String::from("./Setup.t.sol")
+                    }
+                },
+                Issue {
+                    path: PathBuf::from("handlers/HandlersC.t.sol"),
+                    kind: IssueKind::NotInherited {
+                        contract: String::from("HandlersC"),
+                        parent: String::from("HandlersParent")
+                    }
+                },
+            ]
+        );
+
+        Ok(())
+    }
+}
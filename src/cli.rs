@@ -1,6 +1,39 @@
-use clap::{crate_authors, Parser};
+use crate::config::ProjectConfig;
+use crate::foundry::FoundryConfig;
+use crate::{echidna, medusa};
 
-#[derive(Parser)]
+use clap::{crate_authors, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Solidity version used when neither --solc nor foundry.toml set one
+pub const DEFAULT_SOLC: &str = "0.8.23";
+
+/// Fuzz suite directory used when neither --out nor foundry.toml set one
+pub const DEFAULT_OUT: &str = "./test/invariants/fuzz";
+
+/// Sources directory used when neither --src nor foundry.toml set one
+pub const DEFAULT_SRC: &str = "src";
+
+/// Foundry artifacts directory used when neither --artifacts nor foundry.toml set one
+pub const DEFAULT_ARTIFACTS: &str = "out";
+
+/// Number of handlers or properties generated when no name nor number is given
+pub const DEFAULT_COUNT: u8 = 2;
+
+/// SPDX license identifier used when neither --license nor medusa-gen.toml set one
+pub const DEFAULT_LICENSE: &str = "MIT";
+
+/// Number of fuzzer workers used when neither --workers nor medusa-gen.toml set one
+pub const DEFAULT_WORKERS: u8 = 10;
+
+/// Maximum call sequence length used when neither --seq-len nor medusa-gen.toml set one
+pub const DEFAULT_SEQ_LEN: u32 = 100;
+
+/// Corpus directory used when neither --corpus-dir nor medusa-gen.toml set one
+pub const DEFAULT_CORPUS_DIR: &str = "corpus";
+
+#[derive(Parser, Clone)]
 #[command(
     name = "youdusa",
     author = crate_authors!(",\n"),
@@ -27,19 +60,353 @@ Version: {version}
 For more information, visit: https://github.com/defi-wonderland/medusa-gen-rs\n",
 ))]
 pub struct Args {
-    /// Solidity version
-    #[arg(short, long, default_value = "0.8.23")]
-    pub solc: String,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Solidity version or pragma range (eg 0.8.23, ^0.8.0 or ">=0.8.0 <0.9.0")
+    /// [default: foundry.toml solc_version, or 0.8.23]
+    #[arg(short, long, global = true)]
+    pub solc: Option<String>,
+
+    /// Number of handler to generate [default: 2]
+    #[arg(short = 'n', long, value_parser = clap::value_parser!(u8).range(1..))]
+    pub nb_handlers: Option<u8>,
+
+    /// Names of the handlers to generate (eg Vault,Oracle gives HandlersVault and HandlersOracle)
+    #[arg(long, value_delimiter = ',', conflicts_with = "nb_handlers")]
+    pub handlers: Vec<String>,
+
+    /// Number of properties contract to generate [default: 2]
+    #[arg(short = 'p', long, value_parser = clap::value_parser!(u8).range(1..))]
+    pub nb_properties: Option<u8>,
+
+    /// Names of the properties contracts to generate (eg Solvency,AccessControl)
+    #[arg(long, value_delimiter = ',', conflicts_with = "nb_properties")]
+    pub properties: Vec<String>,
+
+    /// ABI of a contract to fuzz (Foundry artifact or bare ABI json), generates one handler per target
+    /// instead of the --nb-handlers empty ones
+    #[arg(long = "abi", value_name = "PATH")]
+    pub abis: Vec<PathBuf>,
+
+    /// Contract to deploy in Setup and generate a handler for, as <path>:<Contract> (eg
+    /// src/Vault.sol:Vault), its ABI is read from the Foundry artifacts
+    #[arg(long = "target", value_name = "PATH:CONTRACT")]
+    pub targets: Vec<String>,
+
+    /// Built-in properties of a standard, checked against a target, as <standard>:<Target> (eg
+    /// erc20:Token), the standards are erc20, erc4626 and erc721
+    #[arg(long = "property-pack", value_name = "STANDARD:TARGET")]
+    pub property_packs: Vec<String>,
+
+    /// Also generate a FuzzTestFoundry entry point running the handlers and properties as Foundry
+    /// invariant tests (requires forge-std)
+    #[arg(long, default_value_t = false, overrides_with = "no_foundry")]
+    pub foundry: bool,
+
+    /// Don't generate the FuzzTestFoundry entry point, even if medusa-gen.toml enables it
+    #[arg(long, default_value_t = false, overrides_with = "foundry")]
+    pub no_foundry: bool,
+
+    /// Rename the generated handler functions colliding with a function of another handler (eg
+    /// handler_vault_deposit_2) instead of failing, every rename is reported
+    #[arg(long, default_value_t = false, overrides_with = "no_rename_collisions")]
+    pub rename_collisions: bool,
 
-    /// Number of handler to generate
-    #[arg(short = 'n', long, default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..))]
-    pub nb_handlers: u8,
+    /// Fail on the handler function collisions, even if medusa-gen.toml enables the renaming
+    #[arg(long, default_value_t = false, overrides_with = "rename_collisions")]
+    pub no_rename_collisions: bool,
 
-    /// Number of properties contract to generate
-    #[arg(short = 'p', long, default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..))]
-    pub nb_properties: u8,
+    /// Fuzzer(s) to generate the config and property prefix for [default: medusa]
+    #[arg(long, value_enum, global = true)]
+    pub fuzzer: Option<Fuzzer>,
+
+    /// Number of actors to call the targets from, adds an Actors contract between Setup and the
+    /// handlers, and a modifier pranking a fuzzed actor on every target handler function
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..))]
+    pub actors: Option<u8>,
+
+    /// Foundry artifacts directory, where the --target ABIs are read
+    /// [default: foundry.toml out, or out]
+    #[arg(long)]
+    pub artifacts: Option<PathBuf>,
+
+    /// Output directory of the fuzz test suite
+    /// [default: <foundry.toml test>/invariants/fuzz, or ./test/invariants/fuzz]
+    #[arg(long, visible_alias = "root", global = true)]
+    pub out: Option<PathBuf>,
+
+    /// Sources directory, used to import targets loaded from a bare ABI
+    /// [default: foundry.toml src, or src]
+    #[arg(long)]
+    pub src: Option<PathBuf>,
+
+    /// SPDX license identifier of the generated contracts [default: MIT]
+    #[arg(long, global = true)]
+    pub license: Option<String>,
+
+    /// Directory of user templates overriding the embedded one, by contract type: handler.sol,
+    /// property.sol, setup.sol, actors.sol, ghosts.sol, entrypoint.sol,
+    /// foundry.sol and repro.sol
+    #[arg(long, global = true)]
+    pub templates: Option<PathBuf>,
 
     /// Overwrite existing files
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, global = true)]
     pub overwrite: bool,
+
+    /// Replace the existing fuzzer configs (medusa.json, echidna.yaml), which are kept otherwise
+    #[arg(long, default_value_t = false)]
+    pub overwrite_config: bool,
+
+    /// Print the files which would be written, with a diff against the existing ones, without
+    /// writing anything
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+
+    /// Merge the template changes since the last generation into the existing files instead of
+    /// replacing them, conflicting changes are left with conflict markers
+    #[arg(long, default_value_t = false, conflicts_with = "overwrite")]
+    pub merge: bool,
+
+    /// Only rewrite the generated regions (between "// medusa-gen:begin <id>" and
+    /// "// medusa-gen:end <id>") of the existing files, keeping everything else
+    #[arg(long, default_value_t = false, conflicts_with_all = ["overwrite", "merge"])]
+    pub regions: bool,
+
+    #[command(flatten)]
+    pub medusa: MedusaArgs,
+}
+
+#[derive(Subcommand, Clone)]
+pub enum Command {
+    /// Add a handler or property contract to an existing suite, updating its parent contract
+    Add {
+        /// Type of contract to add
+        kind: ContractKind,
+
+        /// Name of the contract (eg Foo gives HandlersFoo)
+        name: String,
+    },
+    /// Generate a Foundry test replaying a Medusa call sequence (from the corpus test_results)
+    Repro {
+        /// Call sequence json written by Medusa
+        sequence: PathBuf,
+
+        /// Property to assert after the calls [default: every property of the suite]
+        #[arg(long)]
+        property: Option<String>,
+    },
+    /// Check the inheritance tree, imports, pragmas and function names of an existing suite
+    Check,
+    /// Generate a suite and the medusa-gen.toml regenerating it
+    Init {
+        /// Ask the options one by one (solc, output directory, contracts to fuzz from src, names,
+        /// actors and fuzzer) instead of reading them from the command line
+        #[arg(short, long)]
+        interactive: bool,
+    },
+}
+
+/// The contract types which can be added to an existing suite
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ContractKind {
+    Handler,
+    Property,
+}
+
+/// The fuzzers the suite is generated for
+#[derive(ValueEnum, Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Fuzzer {
+    #[default]
+    Medusa,
+    Echidna,
+    /// Both fuzzers, Echidna is configured to test the Medusa property prefix
+    Both,
+}
+
+impl Fuzzer {
+    /// Whether a medusa.json is generated
+    pub fn medusa(&self) -> bool {
+        *self != Fuzzer::Echidna
+    }
+
+    /// Whether an echidna.yaml is generated
+    pub fn echidna(&self) -> bool {
+        *self != Fuzzer::Medusa
+    }
+
+    /// The prefix of the generated property functions
+    pub fn property_prefix(&self) -> &'static str {
+        match self {
+            Fuzzer::Echidna => echidna::PROPERTY_PREFIX,
+            Fuzzer::Medusa | Fuzzer::Both => medusa::PROPERTY_PREFIX,
+        }
+    }
+}
+
+/// The Echidna test modes, property and assertion testing can't run in the same campaign
+#[derive(ValueEnum, Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EchidnaTestMode {
+    #[default]
+    Property,
+    Assertion,
+}
+
+impl EchidnaTestMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EchidnaTestMode::Property => "property",
+            EchidnaTestMode::Assertion => "assertion",
+        }
+    }
+}
+
+impl Args {
+    /// Fill the options not set on the command line with the medusa-gen.toml values
+    pub fn with_project_config(mut self, config: &ProjectConfig) -> Args {
+        self.solc = self.solc.or_else(|| config.solc.clone());
+        self.out = self.out.or_else(|| config.out.clone());
+        self.src = self.src.or_else(|| config.src.clone());
+        self.artifacts = self.artifacts.or_else(|| config.artifacts.clone());
+        self.license = self.license.or_else(|| config.license.clone());
+        self.templates = self.templates.or_else(|| config.templates.clone());
+
+        // an explicit number of contracts takes precedence over the configured names
+        if self.handlers.is_empty() && self.nb_handlers.is_none() {
+            self.handlers = config.handlers.clone();
+        }
+
+        if self.properties.is_empty() && self.nb_properties.is_none() {
+            self.properties = config.properties.clone();
+        }
+
+        if self.abis.is_empty() {
+            self.abis = config.abis.clone();
+        }
+
+        if self.targets.is_empty() {
+            self.targets = config.targets.clone();
+        }
+
+        if self.property_packs.is_empty() {
+            self.property_packs = config.property_packs.clone();
+        }
+
+        self.actors = self.actors.or(config.actors);
+        self.fuzzer = self.fuzzer.or(config.fuzzer);
+        self.foundry = !self.no_foundry && (self.foundry || config.foundry.unwrap_or_default());
+        self.rename_collisions = !self.no_rename_collisions
+            && (self.rename_collisions || config.rename_collisions.unwrap_or_default());
+
+        self.medusa.workers = self.medusa.workers.or(config.workers);
+        self.medusa.test_limit = self.medusa.test_limit.or(config.test_limit);
+        self.medusa.timeout = self.medusa.timeout.or(config.timeout);
+        self.medusa.seq_len = self.medusa.seq_len.or(config.seq_len);
+        self.medusa.corpus_dir = self.medusa.corpus_dir.or_else(|| config.corpus_dir.clone());
+        self.medusa.echidna_test_mode = self.medusa.echidna_test_mode.or(config.echidna_test_mode);
+
+        self
+    }
+
+    /// Fill the options not set on the command line with the foundry.toml values
+    pub fn with_foundry_defaults(mut self, foundry: &FoundryConfig) -> Args {
+        self.solc = self.solc.or_else(|| foundry.solc_version());
+        self.out = self.out.or_else(|| foundry.fuzz_dir());
+        self.src = self.src.or_else(|| foundry.src());
+        self.artifacts = self.artifacts.or_else(|| foundry.artifacts());
+        self
+    }
+
+    pub fn nb_handlers(&self) -> u8 {
+        self.nb_handlers.unwrap_or(DEFAULT_COUNT)
+    }
+
+    pub fn nb_properties(&self) -> u8 {
+        self.nb_properties.unwrap_or(DEFAULT_COUNT)
+    }
+
+    pub fn fuzzer(&self) -> Fuzzer {
+        self.fuzzer.unwrap_or_default()
+    }
+
+    pub fn license(&self) -> &str {
+        self.license.as_deref().unwrap_or(DEFAULT_LICENSE)
+    }
+
+    pub fn solc(&self) -> &str {
+        self.solc.as_deref().unwrap_or(DEFAULT_SOLC)
+    }
+
+    pub fn out(&self) -> &Path {
+        self.out.as_deref().unwrap_or(Path::new(DEFAULT_OUT))
+    }
+
+    pub fn src(&self) -> &Path {
+        self.src.as_deref().unwrap_or(Path::new(DEFAULT_SRC))
+    }
+
+    pub fn artifacts(&self) -> &Path {
+        self.artifacts
+            .as_deref()
+            .unwrap_or(Path::new(DEFAULT_ARTIFACTS))
+    }
+}
+
+/// The medusa.json knobs, shared with echidna.yaml
+#[derive(clap::Args, Clone, Debug, Default, PartialEq)]
+#[command(next_help_heading = "Fuzzer config")]
+pub struct MedusaArgs {
+    /// Number of fuzzer workers [default: 10]
+    #[arg(long)]
+    pub workers: Option<u8>,
+
+    /// Number of transactions to test before exiting (0 for no limit) [default: 0]
+    #[arg(long)]
+    pub test_limit: Option<u64>,
+
+    /// Duration of the campaign, in seconds (0 for no limit) [default: 0]
+    #[arg(long)]
+    pub timeout: Option<u64>,
+
+    /// Maximum number of calls in a sequence [default: 100]
+    #[arg(long)]
+    pub seq_len: Option<u32>,
+
+    /// Directory where the corpus is stored, relative to the project root [default: corpus]
+    #[arg(long)]
+    pub corpus_dir: Option<String>,
+
+    /// Echidna test mode, Medusa tests both the properties and the assertions
+    /// [default: property]
+    #[arg(long, value_enum)]
+    pub echidna_test_mode: Option<EchidnaTestMode>,
+}
+
+impl MedusaArgs {
+    pub fn workers(&self) -> u8 {
+        self.workers.unwrap_or(DEFAULT_WORKERS)
+    }
+
+    pub fn test_limit(&self) -> u64 {
+        self.test_limit.unwrap_or_default()
+    }
+
+    pub fn timeout(&self) -> u64 {
+        self.timeout.unwrap_or_default()
+    }
+
+    pub fn seq_len(&self) -> u32 {
+        self.seq_len.unwrap_or(DEFAULT_SEQ_LEN)
+    }
+
+    pub fn corpus_dir(&self) -> &str {
+        self.corpus_dir.as_deref().unwrap_or(DEFAULT_CORPUS_DIR)
+    }
+
+    pub fn echidna_test_mode(&self) -> EchidnaTestMode {
+        self.echidna_test_mode.unwrap_or_default()
+    }
 }
@@ -0,0 +1,277 @@
+use crate::check;
+use crate::error::{MedusaGenError, MedusaGenResult};
+use crate::regions;
+use crate::types::ContractType;
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// A generated handler function renamed as its name was already declared by a handler
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rename {
+    pub contract: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// The functions declared by the handler contracts, which share a single namespace once
+/// flattened into HandlersParent
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Namespace {
+    /// The contract declaring each function first
+    declared: BTreeMap<String, String>,
+    /// The parameter types of the generated functions claimed under each name
+    overloads: BTreeMap<String, Vec<String>>,
+    /// Rename the generated functions colliding with a declared one instead of failing
+    rename: bool,
+    renames: Vec<Rename>,
+}
+
+impl Namespace {
+    pub fn new(rename: bool) -> Namespace {
+        Namespace {
+            rename,
+            ..Default::default()
+        }
+    }
+
+    fn collision(&self, function: &str, contract: &str) -> MedusaGenError {
+        MedusaGenError::FunctionCollision {
+            function: function.to_string(),
+            first: self.declared[function].clone(),
+            second: contract.to_string(),
+        }
+    }
+
+    /// Declare a function written by hand (or in a template), which can't be renamed. Declaring
+    /// it again in the same contract is an overload
+    pub fn declare(&mut self, contract: &str, function: &str) -> MedusaGenResult<()> {
+        match self.declared.get(function) {
+            Some(first) if first != contract => Err(self.collision(function, contract)),
+            Some(_) => Ok(()),
+            None => {
+                self.declared
+                    .insert(function.to_string(), contract.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    /// Whether a generated function taking `params` can be named `function` in `contract`: the
+    /// name is free, or an overload of the functions generated in the same contract
+    fn is_available(&self, contract: &str, function: &str, params: &str) -> bool {
+        match self.declared.get(function) {
+            None => true,
+            Some(first) => {
+                first == contract
+                    && self
+                        .overloads
+                        .get(function)
+                        .is_some_and(|claimed| !claimed.iter().any(|types| types == params))
+            }
+        }
+    }
+
+    /// Claim the name of a generated function taking `params` (eg "uint256,bytes"), which can
+    /// overload the ones generated in the same contract. A name already declared otherwise is
+    /// suffixed with the first available number when renaming
+    pub fn claim(
+        &mut self,
+        contract: &str,
+        function: &str,
+        params: &str,
+    ) -> MedusaGenResult<String> {
+        let to = if self.is_available(contract, function, params) {
+            function.to_string()
+        } else if self.rename {
+            (2..)
+                .map(|i| format!("{}_{}", function, i))
+                .find(|name| self.is_available(contract, name, params))
+                .unwrap_or_default()
+        } else {
+            return Err(self.collision(function, contract));
+        };
+
+        self.declared
+            .entry(to.clone())
+            .or_insert_with(|| contract.to_string());
+        self.overloads
+            .entry(to.clone())
+            .or_default()
+            .push(params.to_string());
+
+        if to == function {
+            return Ok(to);
+        }
+
+        self.renames.push(Rename {
+            contract: contract.to_string(),
+            from: function.to_string(),
+            to: to.clone(),
+        });
+
+        Ok(to)
+    }
+
+    /// The generated functions renamed, in order
+    pub fn into_renames(self) -> Vec<Rename> {
+        self.renames
+    }
+}
+
+/// The (contract, function) names written by hand in the existing handler contracts of `out`,
+/// ie outside of their generated regions, HandlersParent left aside
+pub fn existing_functions(out: &Path) -> Result<Vec<(String, String)>> {
+    let dir = out.join(ContractType::Handler.directory_name());
+
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = fs::read_dir(&dir)
+        .context(format!("Failed to read {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.sort();
+
+    let parent = format!("{}.t.sol", ContractType::Handler.inherited_name());
+    let mut functions = Vec::new();
+
+    for path in paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        if !name.ends_with(".t.sol") || name == parent {
+            continue;
+        }
+
+        let source =
+            fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?;
+        let source =
+            regions::strip(&source).context(format!("Invalid regions in {}", path.display()))?;
+
+        functions.extend(check::contract_functions(&source));
+    }
+
+    Ok(functions)
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_namespace() -> MedusaGenResult<()> {
+        let mut namespace = Namespace::new(false);
+
+        namespace.declare("HandlersA", "_bound")?;
+        namespace.declare("HandlersA", "_bound")?;
+        assert!(matches!(
+            namespace.declare("HandlersB", "_bound"),
+            Err(MedusaGenError::FunctionCollision { function, first, second })
+                if function == "_bound" && first == "HandlersA" && second == "HandlersB"
+        ));
+
+        assert_eq!(
+            namespace.claim("HandlersVault", "handler_vault_deposit", "uint256")?,
+            "handler_vault_deposit"
+        );
+
+        // an overload of the same contract, but not of another one
+        assert_eq!(
+            namespace.claim("HandlersVault", "handler_vault_deposit", "uint256,address")?,
+            "handler_vault_deposit"
+        );
+        assert!(namespace
+            .claim("HandlersVault", "handler_vault_deposit", "uint256")
+            .is_err());
+        assert!(matches!(
+            namespace.claim("HandlersOracle", "handler_vault_deposit", "bytes"),
+            Err(MedusaGenError::FunctionCollision { first, second, .. })
+                if first == "HandlersVault" && second == "HandlersOracle"
+        ));
+
+        // a hand-written function can't be overloaded, its parameters being unknown
+        namespace.declare("HandlersOracle", "handler_oracle_update")?;
+        assert!(namespace
+            .claim("HandlersOracle", "handler_oracle_update", "uint256")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_namespace_rename() -> MedusaGenResult<()> {
+        let mut namespace = Namespace::new(true);
+
+        // a function of another handler, then a hand-written one taking the suffix
+        namespace.claim("HandlersA", "handler_vault_deposit", "uint256")?;
+        namespace.declare("HandlersA", "handler_vault_deposit_2")?;
+
+        assert_eq!(
+            namespace.claim("HandlersVault", "handler_vault_deposit", "uint256")?,
+            "handler_vault_deposit_3"
+        );
+
+        // the overloads are renamed alike
+        assert_eq!(
+            namespace.claim("HandlersVault", "handler_vault_deposit", "uint256,bytes")?,
+            "handler_vault_deposit_3"
+        );
+
+        // the same parameters as an already renamed function
+        assert_eq!(
+            namespace.claim("HandlersVault", "handler_vault_deposit", "uint256")?,
+            "handler_vault_deposit_4"
+        );
+
+        assert_eq!(
+            namespace
+                .into_renames()
+                .iter()
+                .map(|rename| rename.to.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "handler_vault_deposit_3",
+                "handler_vault_deposit_3",
+                "handler_vault_deposit_4"
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_existing_functions() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path().join("handlers");
+        fs::create_dir_all(&dir)?;
+
+        fs::write(
+            dir.join("HandlersVault.t.sol"),
+            "contract HandlersVault is Setup {
+  // medusa-gen:begin handlers
+  function handler_vault_deposit(uint256 _assets) public {}
+  // medusa-gen:end handlers
+
+  function _bound(uint256 _value) internal {}
+}",
+        )?;
+        fs::write(
+            dir.join("HandlersParent.t.sol"),
+            "contract HandlersParent is HandlersVault {\n  function parent() public {}\n}",
+        )?;
+
+        assert_eq!(
+            existing_functions(temp_dir.path())?,
+            vec![(String::from("HandlersVault"), String::from("_bound"))]
+        );
+        assert!(existing_functions(&temp_dir.path().join("missing"))?.is_empty());
+
+        Ok(())
+    }
+}
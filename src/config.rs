@@ -0,0 +1,214 @@
+use crate::cli::{Args, EchidnaTestMode, Fuzzer};
+use crate::suite::SuiteSpec;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the project config file, looked up in the project root
+pub const CONFIG_FILE_NAME: &str = "medusa-gen.toml";
+
+/// The medusa-gen.toml content, versioning the suite layout. Every entry is optional and
+/// overridden by the matching command line option
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub out: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub src: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub handlers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub abis: Vec<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub property_packs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actors: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fuzzer: Option<Fuzzer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foundry: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rename_collisions: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artifacts: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub templates: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workers: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq_len: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub corpus_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub echidna_test_mode: Option<EchidnaTestMode>,
+}
+
+/// The config regenerating the suite of `args`, the numbered contracts being named after their
+/// letter
+impl From<&Args> for ProjectConfig {
+    fn from(args: &Args) -> Self {
+        let spec = SuiteSpec::from(args);
+
+        ProjectConfig {
+            solc: args.solc.clone(),
+            out: args.out.clone(),
+            src: args.src.clone(),
+            handlers: spec.handlers,
+            properties: spec.properties,
+            abis: args.abis.clone(),
+            targets: args.targets.clone(),
+            property_packs: args.property_packs.clone(),
+            actors: args.actors,
+            fuzzer: args.fuzzer,
+            foundry: args.foundry.then_some(true),
+            rename_collisions: args.rename_collisions.then_some(true),
+            artifacts: args.artifacts.clone(),
+            license: args.license.clone(),
+            templates: args.templates.clone(),
+            workers: args.medusa.workers,
+            test_limit: args.medusa.test_limit,
+            timeout: args.medusa.timeout,
+            seq_len: args.medusa.seq_len,
+            corpus_dir: args.medusa.corpus_dir.clone(),
+            echidna_test_mode: args.medusa.echidna_test_mode,
+        }
+    }
+}
+
+impl ProjectConfig {
+    /// Load the medusa-gen.toml in `root`, if any
+    pub fn load(root: &Path) -> Result<Option<ProjectConfig>> {
+        let path = root.join(CONFIG_FILE_NAME);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content =
+            fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?;
+
+        toml::from_str(&content)
+            .context(format!("Failed to parse {}", path.display()))
+            .map(Some)
+    }
+
+    /// Write the config in `root`
+    pub fn write(&self, root: &Path) -> Result<()> {
+        let path = root.join(CONFIG_FILE_NAME);
+        let content = toml::to_string(self).context("Failed to serialize the project config")?;
+
+        fs::write(&path, content).context(format!("Failed to write {}", path.display()))
+    }
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join(CONFIG_FILE_NAME),
+            r#"
+solc = "^0.8.0"
+out = "packages/vault/test/fuzz"
+handlers = ["Vault", "Oracle"]
+abis = ["out/Vault.sol/Vault.json"]
+license = "GPL-3.0"
+"#,
+        )?;
+
+        let config = ProjectConfig::load(temp_dir.path())?.unwrap();
+
+        assert_eq!(
+            config,
+            ProjectConfig {
+                solc: Some(String::from("^0.8.0")),
+                out: Some(PathBuf::from("packages/vault/test/fuzz")),
+                handlers: vec![String::from("Vault"), String::from("Oracle")],
+                abis: vec![PathBuf::from("out/Vault.sol/Vault.json")],
+                license: Some(String::from("GPL-3.0")),
+                ..Default::default()
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let args = Args::parse_from([
+            "medusa-gen",
+            "--solc",
+            "0.8.26",
+            "-n",
+            "2",
+            "--properties",
+            "Solvency",
+            "--actors",
+            "3",
+            "--fuzzer",
+            "both",
+            "--artifacts",
+            "build",
+            "--timeout",
+            "3600",
+            "--echidna-test-mode",
+            "assertion",
+        ]);
+
+        let config = ProjectConfig::from(&args);
+        config.write(temp_dir.path())?;
+
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(CONFIG_FILE_NAME))?,
+            "solc = \"0.8.26\"\nhandlers = [\"A\", \"B\"]\nproperties = [\"Solvency\"]\nactors = 3\nfuzzer = \"both\"\nartifacts = \"build\"\ntimeout = 3600\nechidna_test_mode = \"assertion\"\n"
+        );
+        assert_eq!(ProjectConfig::load(temp_dir.path())?, Some(config));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_missing() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+
+        assert_eq!(ProjectConfig::load(temp_dir.path())?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_unknown_key() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(
+            temp_dir.path().join(CONFIG_FILE_NAME),
+            "handler = [\"Vault\"]",
+        )?;
+
+        assert!(ProjectConfig::load(temp_dir.path()).is_err());
+
+        Ok(())
+    }
+}
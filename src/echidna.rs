@@ -0,0 +1,82 @@
+use crate::cli::MedusaArgs;
+use crate::types::ContractType;
+
+use anyhow::{Context, Result};
+use askama::Template;
+
+/// Prefix of the property functions, as tested by Echidna by default
+pub const PROPERTY_PREFIX: &str = "echidna_";
+
+/// Name of the generated Echidna config file, written in the project root
+pub const CONFIG_FILE_NAME: &str = "echidna.yaml";
+
+/// The echidna.yaml content, sharing the campaign settings of medusa.json
+#[derive(Template, Debug, Clone, PartialEq)]
+#[template(path = "echidna.yaml", escape = "none")]
+pub struct EchidnaConfig {
+    pub contract: String,
+    pub test_mode: String,
+    pub prefix: String,
+    pub test_limit: u64,
+    pub timeout: u64,
+    pub seq_len: u32,
+    pub workers: u8,
+    pub corpus_dir: String,
+}
+
+impl EchidnaConfig {
+    /// Build the config testing the properties with `prefix` on the generated entry point
+    pub fn new(args: &MedusaArgs, prefix: &str, corpus_dir: String) -> EchidnaConfig {
+        EchidnaConfig {
+            contract: ContractType::EntryPoint.name().to_string(),
+            test_mode: args.echidna_test_mode().name().to_string(),
+            prefix: prefix.to_string(),
+            test_limit: args.test_limit(),
+            timeout: args.timeout(),
+            seq_len: args.seq_len(),
+            workers: args.workers(),
+            corpus_dir,
+        }
+    }
+
+    /// Render the config as yaml
+    pub fn render_yaml(&self) -> Result<String> {
+        self.render()
+            .context("Failed to render echidna config")
+            .map(|rendered| rendered + "\n")
+    }
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let args = MedusaArgs {
+            timeout: Some(3600),
+            ..MedusaArgs::default()
+        };
+
+        assert_eq!(
+            EchidnaConfig::new(&args, PROPERTY_PREFIX, String::from("corpus")).render_yaml()?,
+            r#"# run with: echidna . --contract FuzzTest --config echidna.yaml
+testMode: "property"
+prefix: "echidna_"
+timeout: 3600
+seqLen: 100
+workers: 10
+corpusDir: "corpus"
+deployer: "0x30000"
+sender: ["0x10000", "0x20000", "0x30000"]
+maxBlockDelay: 60480
+maxTimeDelay: 604800
+cryticArgs: ["--foundry-compile-all"]
+"#
+        );
+
+        Ok(())
+    }
+}
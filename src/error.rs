@@ -0,0 +1,119 @@
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// The errors of the library API, the ones callers can react to have their own variant, others
+/// are reported as is
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum MedusaGenError {
+    /// A file or directory would be overwritten without --overwrite
+    #[error("{} already exists, did you mean --overwrite ?", path.display())]
+    DestinationExists { path: PathBuf },
+
+    /// The suite to update hasn't been generated
+    #[error("{} not found, did you generate the suite first ?", path.display())]
+    SuiteNotFound { path: PathBuf },
+
+    #[error("{name} is not a valid Solidity identifier")]
+    InvalidIdentifier { name: String },
+
+    #[error("{name} is a reserved Solidity keyword")]
+    ReservedKeyword { name: String },
+
+    #[error("Duplicate name: {0}")]
+    DuplicateName(String),
+
+    #[error("Invalid solc version pragma: {0}")]
+    InvalidPragma(String),
+
+    #[error("{parent} already inherits {child}")]
+    AlreadyInherited { parent: String, child: String },
+
+    /// Two handler contracts declare the same function, which fails to compile once both are
+    /// inherited by HandlersParent
+    #[error(
+        "{function} is declared in both {first} and {second}, did you mean --rename-collisions ?"
+    )]
+    FunctionCollision {
+        function: String,
+        first: String,
+        second: String,
+    },
+
+    /// `medusa-gen check` found issues in the suite
+    #[error("{count} issue(s) found in the suite")]
+    CheckFailed { count: usize },
+
+    #[error("Template directory {} not found", path.display())]
+    TemplateDirectoryNotFound { path: PathBuf },
+
+    #[error("Failed to render {contract}")]
+    TemplateRender {
+        contract: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[error("Failed to access {}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+/// Recover the typed error an internal error was raised with, unless context was added on the
+/// way up, which is kept by wrapping the whole chain
+impl From<anyhow::Error> for MedusaGenError {
+    fn from(error: anyhow::Error) -> Self {
+        let is_typed = error
+            .chain()
+            .next()
+            .is_some_and(|outermost| outermost.is::<MedusaGenError>());
+
+        if is_typed {
+            error
+                .downcast::<MedusaGenError>()
+                .unwrap_or_else(MedusaGenError::Other)
+        } else {
+            MedusaGenError::Other(error)
+        }
+    }
+}
+
+pub type MedusaGenResult<T> = std::result::Result<T, MedusaGenError>;
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_from_anyhow() {
+        let typed = anyhow::Error::from(MedusaGenError::DuplicateName(String::from("Vault")));
+
+        assert!(matches!(
+            MedusaGenError::from(typed),
+            MedusaGenError::DuplicateName(name) if name == "Vault"
+        ));
+
+        // the context is kept, along the typed error
+        let typed: anyhow::Result<()> = Err(MedusaGenError::DuplicateName(String::from("Vault")))
+            .context("Invalid handler name");
+        let error = MedusaGenError::from(typed.unwrap_err());
+        assert!(matches!(error, MedusaGenError::Other(_)));
+        assert_eq!(
+            format!("{:#}", error),
+            "Invalid handler name: Duplicate name: Vault"
+        );
+
+        let other = MedusaGenError::from(anyhow::anyhow!("Failed to parse ABI"));
+        assert!(matches!(other, MedusaGenError::Other(_)));
+        assert_eq!(other.to_string(), "Failed to parse ABI");
+    }
+}
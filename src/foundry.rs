@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the Foundry config file, looked up in the project root
+pub const CONFIG_FILE_NAME: &str = "foundry.toml";
+
+/// The subset of a Foundry profile medusa-gen cares about
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct FoundryProfile {
+    pub solc_version: Option<String>,
+    pub solc: Option<String>,
+    pub src: Option<PathBuf>,
+    pub test: Option<PathBuf>,
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct FoundryToml {
+    #[serde(default)]
+    profile: HashMap<String, FoundryProfile>,
+}
+
+/// The resolved Foundry config: the selected profile on top of the default one
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FoundryConfig {
+    pub profile: FoundryProfile,
+}
+
+impl FoundryConfig {
+    /// Load the foundry.toml in `root`, if any, using the FOUNDRY_PROFILE profile (or "default")
+    pub fn load(root: &Path) -> Result<Option<FoundryConfig>> {
+        let path = root.join(CONFIG_FILE_NAME);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content =
+            fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?;
+        let profile = std::env::var("FOUNDRY_PROFILE").unwrap_or_else(|_| String::from("default"));
+
+        FoundryConfig::parse(&content, &profile)
+            .context(format!("Failed to parse {}", path.display()))
+            .map(Some)
+    }
+
+    /// Parse a foundry.toml content, overriding the default profile with the given one
+    pub fn parse(content: &str, profile: &str) -> Result<FoundryConfig> {
+        let mut parsed: FoundryToml = toml::from_str(content)?;
+
+        let default = parsed.profile.remove("default").unwrap_or_default();
+        let selected = parsed.profile.remove(profile).unwrap_or_default();
+
+        Ok(FoundryConfig {
+            profile: FoundryProfile {
+                solc_version: selected.solc_version.or(default.solc_version),
+                solc: selected.solc.or(default.solc),
+                src: selected.src.or(default.src),
+                test: selected.test.or(default.test),
+                out: selected.out.or(default.out),
+            },
+        })
+    }
+
+    /// The solc version, from `solc_version` or `solc` when it is a version (and not a path)
+    pub fn solc_version(&self) -> Option<String> {
+        self.profile.solc_version.clone().or_else(|| {
+            self.profile
+                .solc
+                .clone()
+                .filter(|solc| crate::types::validate_pragma(solc).is_ok())
+        })
+    }
+
+    /// The fuzz suite directory, inside the test directory
+    pub fn fuzz_dir(&self) -> Option<PathBuf> {
+        self.profile
+            .test
+            .as_ref()
+            .map(|test| test.join("invariants").join("fuzz"))
+    }
+
+    /// The sources directory
+    pub fn src(&self) -> Option<PathBuf> {
+        self.profile.src.clone()
+    }
+
+    /// The compilation artifacts directory
+    pub fn artifacts(&self) -> Option<PathBuf> {
+        self.profile.out.clone()
+    }
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const FOUNDRY_TOML: &str = r#"
+[profile.default]
+solc_version = "0.8.26"
+src = "contracts"
+test = "tests"
+out = "artifacts"
+libs = ["node_modules", "lib"]
+
+[profile.ci]
+solc_version = "0.8.27"
+
+[fmt]
+line_length = 120
+"#;
+
+    #[test]
+    fn test_parse_default_profile() -> Result<()> {
+        let config = FoundryConfig::parse(FOUNDRY_TOML, "default")?;
+
+        assert_eq!(config.solc_version(), Some(String::from("0.8.26")));
+        assert_eq!(config.src(), Some(PathBuf::from("contracts")));
+        assert_eq!(config.artifacts(), Some(PathBuf::from("artifacts")));
+        assert_eq!(
+            config.fuzz_dir(),
+            Some(PathBuf::from("tests/invariants/fuzz"))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_profile_override() -> Result<()> {
+        let config = FoundryConfig::parse(FOUNDRY_TOML, "ci")?;
+
+        assert_eq!(config.solc_version(), Some(String::from("0.8.27")));
+        assert_eq!(config.src(), Some(PathBuf::from("contracts")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_solc_path() -> Result<()> {
+        let config =
+            FoundryConfig::parse("[profile.default]\nsolc = \"/usr/bin/solc\"", "default")?;
+
+        assert_eq!(config.solc_version(), None);
+        assert_eq!(config.src(), None);
+        assert_eq!(config.fuzz_dir(), None);
+
+        let config = FoundryConfig::parse("[profile.default]\nsolc = \"0.8.20\"", "default")?;
+        assert_eq!(config.solc_version(), Some(String::from("0.8.20")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_missing() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+
+        assert_eq!(FoundryConfig::load(temp_dir.path())?, None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+use crate::abi::{AbiFunction, Target};
+
+use std::fmt::Write;
+
+/// The balance-like getters tracked by ghost variables, with their number of (address) parameters
+const TRACKED_GETTERS: [(&str, usize); 3] =
+    [("balanceOf", 1), ("totalSupply", 0), ("totalAssets", 0)];
+
+/// The hook called before the target call in the handlers
+pub const BEFORE: &str = "Before";
+
+/// The hook called after the target call in the handlers
+pub const AFTER: &str = "After";
+
+/// Whether an ABI entry is a tracked getter: `balanceOf(address)`, `totalSupply()` or
+/// `totalAssets()`
+pub fn is_tracked(function: &AbiFunction) -> bool {
+    function.kind == "function"
+        && function.state_mutability == "view"
+        && function.inputs.iter().all(|param| param.kind == "address")
+        && TRACKED_GETTERS
+            .iter()
+            .any(|(name, inputs)| function.name == *name && function.inputs.len() == *inputs)
+}
+
+/// The name of the hook updating the ghost variables of a target (eg "_beforeVault")
+pub fn hook_name(target: &Target, phase: &str) -> String {
+    format!("_{}{}", phase.to_lowercase(), target.name)
+}
+
+/// The ghost variable holding a getter value (eg "ghost_vault_totalSupplyBefore")
+fn variable_name(target: &Target, getter: &str, phase: &str) -> String {
+    format!("ghost_{}_{}{}", target.instance_name(), getter, phase)
+}
+
+/// The Ghosts contract body: a before and after ghost variable per tracked getter, keyed by
+/// account for the balances, and the hooks updating them
+pub fn body(targets: &[Target]) -> String {
+    let tracked: Vec<&Target> = targets
+        .iter()
+        .filter(|target| !target.getters.is_empty())
+        .collect();
+
+    let mut declarations = String::new();
+    let mut hooks = String::new();
+
+    for target in &tracked {
+        for getter in &target.getters {
+            for phase in [BEFORE, AFTER] {
+                let _ = writeln!(
+                    declarations,
+                    "  {} internal {};",
+                    if getter == "balanceOf" {
+                        "mapping(address => uint256)"
+                    } else {
+                        "uint256"
+                    },
+                    variable_name(target, getter, phase)
+                );
+            }
+        }
+
+        for phase in [BEFORE, AFTER] {
+            let _ = writeln!(
+                hooks,
+                "\n  function {}(address _account) internal {{",
+                hook_name(target, phase)
+            );
+
+            for getter in &target.getters {
+                let _ = writeln!(
+                    hooks,
+                    "    {}{} = {}.{}({});",
+                    variable_name(target, getter, phase),
+                    if getter == "balanceOf" {
+                        "[_account]"
+                    } else {
+                        ""
+                    },
+                    target.instance_name(),
+                    getter,
+                    if getter == "balanceOf" {
+                        "_account"
+                    } else {
+                        ""
+                    }
+                );
+            }
+
+            let _ = writeln!(hooks, "  }}");
+        }
+    }
+
+    format!("{}{}", declarations, hooks)
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_is_tracked() {
+        let functions: Vec<AbiFunction> = serde_json::from_str(
+            r#"[
+                {"type": "function", "name": "balanceOf", "inputs": [{"name": "account", "type": "address"}], "stateMutability": "view"},
+                {"type": "function", "name": "totalSupply", "inputs": [], "stateMutability": "view"},
+                {"type": "function", "name": "balanceOf", "inputs": [{"name": "id", "type": "uint256"}], "stateMutability": "view"},
+                {"type": "function", "name": "totalAssets", "inputs": [], "stateMutability": "nonpayable"},
+                {"type": "function", "name": "name", "inputs": [], "stateMutability": "view"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            functions.iter().map(is_tracked).collect::<Vec<_>>(),
+            vec![true, true, false, false, false]
+        );
+    }
+
+    #[test]
+    fn test_body() {
+        let target = |name: &str, getters: &[&str]| Target {
+            name: name.to_string(),
+            source: PathBuf::from(format!("src/{}.sol", name)),
+            constructor: vec![],
+            functions: vec![],
+            getters: getters.iter().map(|getter| getter.to_string()).collect(),
+        };
+
+        assert_eq!(
+            body(&[
+                target("Vault", &["balanceOf", "totalAssets"]),
+                target("Oracle", &[])
+            ]),
+            "  mapping(address => uint256) internal ghost_vault_balanceOfBefore;
+  mapping(address => uint256) internal ghost_vault_balanceOfAfter;
+  uint256 internal ghost_vault_totalAssetsBefore;
+  uint256 internal ghost_vault_totalAssetsAfter;
+
+  function _beforeVault(address _account) internal {
+    ghost_vault_balanceOfBefore[_account] = vault.balanceOf(_account);
+    ghost_vault_totalAssetsBefore = vault.totalAssets();
+  }
+
+  function _afterVault(address _account) internal {
+    ghost_vault_balanceOfAfter[_account] = vault.balanceOf(_account);
+    ghost_vault_totalAssetsAfter = vault.totalAssets();
+  }
+"
+        );
+    }
+}
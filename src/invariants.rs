@@ -0,0 +1,123 @@
+use std::fmt::Write;
+
+/// The forge-std import of the Foundry entry point
+pub const BASE_IMPORT: &str = "import {StdInvariant} from 'forge-std/StdInvariant.sol';\n";
+
+/// The forge-std base of the Foundry entry point. StdInvariant rather than Test: Test inherits
+/// `vm` from CommonBase, which clashes with the `vm` declared by Actors (a state variable can't be
+/// declared twice in a hierarchy). StdInvariant only brings the invariant target selection, so
+/// the suite keeps a single `vm` and nothing but this entry point depends on forge-std
+pub const BASE_NAME: &str = "StdInvariant";
+
+/// Prefix of the invariant functions, as tested by Foundry
+const INVARIANT_PREFIX: &str = "invariant_";
+
+/// The selector of a handler, by signature when it is overloaded as `this.<name>.selector` would
+/// be ambiguous
+fn selector(signature: &str, handlers: &[String]) -> String {
+    let name = |signature: &str| signature.split('(').next().unwrap_or_default().to_string();
+    let overloads = handlers
+        .iter()
+        .filter(|handler| name(handler) == name(signature))
+        .count();
+
+    if overloads > 1 {
+        format!("bytes4(keccak256(\"{}\"))", signature)
+    } else {
+        format!("this.{}.selector", name(signature))
+    }
+}
+
+/// The Foundry entry point body: a setUp fuzzing the handler functions of the suite only (by
+/// signature, eg "handler_deposit(uint256)"), and an invariant asserting every property
+/// (properties starting with `prefix`)
+pub fn body(handlers: &[String], properties: &[String], prefix: &str) -> String {
+    let mut output =
+        String::from("  function setUp() public {\n    targetContract(address(this));\n");
+
+    if !handlers.is_empty() {
+        let _ = writeln!(
+            output,
+            "\n    bytes4[] memory _selectors = new bytes4[]({});",
+            handlers.len()
+        );
+
+        for (i, handler) in handlers.iter().enumerate() {
+            let _ = writeln!(
+                output,
+                "    _selectors[{}] = {};",
+                i,
+                selector(handler, handlers)
+            );
+        }
+
+        let _ = writeln!(
+            output,
+            "\n    targetSelector(FuzzSelector({{addr: address(this), selectors: _selectors}}));"
+        );
+    }
+
+    let _ = writeln!(output, "  }}");
+
+    for property in properties {
+        let _ = write!(
+            output,
+            "
+  function {}{}() public {{
+    require({}(), '{}');
+  }}
+",
+            INVARIANT_PREFIX,
+            property.strip_prefix(prefix).unwrap_or(property),
+            property,
+            property
+        );
+    }
+
+    output
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body() {
+        assert_eq!(
+            body(
+                &[
+                    String::from("handler_deposit(uint256)"),
+                    String::from("handler_withdraw(uint256)"),
+                    String::from("handler_withdraw(uint256,address)")
+                ],
+                &[String::from("property_solvency")],
+                "property_"
+            ),
+            "  function setUp() public {
+    targetContract(address(this));
+
+    bytes4[] memory _selectors = new bytes4[](3);
+    _selectors[0] = this.handler_deposit.selector;
+    _selectors[1] = bytes4(keccak256(\"handler_withdraw(uint256)\"));
+    _selectors[2] = bytes4(keccak256(\"handler_withdraw(uint256,address)\"));
+
+    targetSelector(FuzzSelector({addr: address(this), selectors: _selectors}));
+  }
+
+  function invariant_solvency() public {
+    require(property_solvency(), 'property_solvency');
+  }
+"
+        );
+    }
+
+    #[test]
+    fn test_body_empty() {
+        assert_eq!(
+            body(&[], &[], "property_"),
+            "  function setUp() public {\n    targetContract(address(this));\n  }\n"
+        );
+    }
+}
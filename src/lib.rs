@@ -1,15 +1,52 @@
+mod abi;
+mod actors;
+mod cheatcodes;
+pub mod check;
 pub mod cli;
+pub mod collisions;
+mod config;
+mod echidna;
+pub mod error;
+mod foundry;
+mod ghosts;
+mod invariants;
+mod medusa;
+pub mod merge;
+mod packs;
+mod preview;
+pub mod regions;
+mod repro;
+pub mod sink;
+pub mod suite;
+mod templates;
 mod types;
-
-use crate::cli::Args;
-use crate::types::{Contract, ContractBuilder, ContractType};
+mod update;
+pub mod wizard;
+
+use crate::abi::Target;
+use crate::cli::{Args, ContractKind};
+use crate::collisions::Namespace;
+use crate::config::ProjectConfig;
+use crate::echidna::EchidnaConfig;
+use crate::error::MedusaGenResult;
+use crate::foundry::FoundryConfig;
+use crate::medusa::MedusaConfig;
+use crate::packs::PropertyPack;
+use crate::templates::Templates;
+use crate::types::{validate_identifier, validate_names, Contract, ContractBuilder, ContractType};
+use crate::wizard::Wizard;
 
 use anyhow::{Context, Result};
-use fs_extra::dir::{copy, CopyOptions};
+use std::collections::BTreeMap;
 use std::fmt::Write;
-use std::fs::DirBuilder;
+use std::io::BufRead;
 use std::path::Path;
-use tempfile::TempDir;
+
+pub use crate::error::MedusaGenError;
+pub use crate::merge::{MergeSink, MergeStatus, StateSink};
+pub use crate::regions::{RegionSink, RegionStatus};
+pub use crate::sink::{DiskSink, MemorySink, Sink, TarSink};
+pub use crate::suite::{GeneratedSuite, SuiteBuilder, SuiteSpec};
 
 /// Create the "import { HandlerA, HandlerB } from './handlers/HandlersParent.t.sol';" from a vec of parent contracts
 fn parse_child_imports(parents: &[Contract]) -> String {
@@ -32,29 +69,127 @@ fn parse_parents(parents: &[Contract]) -> String {
         .to_string()
 }
 
-/// create a vec of contracts of a given type
-fn create_contracts(contract_type: &ContractType, count: u8, path: &Path) -> Result<Vec<Contract>> {
+/// The functions starting with `prefix` declared in the contracts of `dir` (eg the property
+/// functions of the properties contracts)
+fn declared_functions(dir: &Path, prefix: &str) -> Result<Vec<String>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = std::fs::read_dir(dir)
+        .context(format!("Failed to read {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.sort();
+
+    let sources = paths
+        .iter()
+        .filter(|path| path.is_file())
+        .map(|path| {
+            std::fs::read_to_string(path).context(format!("Failed to read {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(functions_declared_in(
+        sources.iter().map(String::as_str),
+        prefix,
+    ))
+}
+
+/// The functions starting with `prefix` declared in the given sources, without duplicates
+fn functions_declared_in<'a>(sources: impl Iterator<Item = &'a str>, prefix: &str) -> Vec<String> {
+    let mut functions: Vec<String> = Vec::new();
+
+    for source in sources {
+        for (i, _) in source.match_indices(&format!("function {}", prefix)) {
+            let start = i + "function ".len();
+            let name = source[start..]
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .next()
+                .unwrap_or_default();
+
+            if !functions.iter().any(|known| known == name) {
+                functions.push(name.to_string());
+            }
+        }
+    }
+
+    functions
+}
+
+/// The signatures of the functions starting with `prefix` declared in the given sources, without
+/// duplicates, the parameter types found in `canonical` being replaced by their signature type
+fn signatures_declared_in<'a>(
+    sources: impl Iterator<Item = &'a str>,
+    prefix: &str,
+    canonical: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let mut signatures: Vec<String> = Vec::new();
+
+    for signature in sources.flat_map(check::function_signatures) {
+        let Some((name, types)) = signature.trim_end_matches(')').split_once('(') else {
+            continue;
+        };
+
+        if !name.starts_with(prefix) {
+            continue;
+        }
+
+        let types = types
+            .split(',')
+            .filter(|kind| !kind.is_empty())
+            .map(|kind| canonical.get(kind).map_or(kind, String::as_str))
+            .collect::<Vec<_>>()
+            .join(",");
+        let signature = format!("{}({})", name, types);
+
+        if !signatures.contains(&signature) {
+            signatures.push(signature);
+        }
+    }
+
+    signatures
+}
+
+/// The "A", "B", ..., "Z", "AA", "AB" suffix of the i-th unnamed contract
+fn letter_suffix(i: usize) -> String {
+    let mut suffix = String::new();
+    let mut n = i + 1;
+
+    while n > 0 {
+        n -= 1;
+        suffix.insert(0, (b'A' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+
+    suffix
+}
+
+/// create a vec of contracts of a given type, one per name suffix (eg "Vault" for "HandlersVault"),
+/// inheriting from `parent`, and render them in the suite
+fn create_contracts(
+    contract_type: &ContractType,
+    parent: &ContractType,
+    names: &[String],
+    base: &ContractBuilder,
+    suite: &mut GeneratedSuite,
+) -> Result<Vec<Contract>> {
     let mut contracts = Vec::new();
 
-    // directories
-    DirBuilder::new()
-        .recursive(true)
-        .create(path)
-        .context(format!(
-            "Failed to create directory for {} contracts",
-            contract_type.directory_name()
-        ))?;
-
-    for i in 0..count {
-        let contract = ContractBuilder::new()
+    for name in names {
+        let contract = base
+            .clone()
             .with_type(contract_type)
-            .with_name(format!("{}{}", contract_type.name(), (b'A' + i) as char))
+            .with_parent(parent)
+            .with_name(format!("{}{}", contract_type.name(), name))
             .build();
 
-        contract.write_rendered_contract(path).context(format!(
-            "Failed to write rendered {} contract",
-            contract_type.name()
-        ))?;
+        suite
+            .insert_contract(contract_type.directory_name(), &contract)
+            .context(format!(
+                "Failed to render {} contract",
+                contract_type.name()
+            ))?;
 
         contracts.push(contract);
     }
@@ -62,119 +197,728 @@ fn create_contracts(contract_type: &ContractType, count: u8, path: &Path) -> Res
     Ok(contracts)
 }
 
-/// Generate parents contracts and write them to a temp folder
-fn generate_parents(
-    contract_type: ContractType,
-    args: &Args,
-    path: &Path,
+/// create one handler contract per target, wrapping every non-view function of its ABI, the
+/// functions are called from a fuzzed actor when `actors` is set. Their names are claimed in the
+/// handlers `namespace`, the `packs` targets keep their recipients among the summed holders
+#[allow(clippy::too_many_arguments)]
+fn create_target_handlers(
+    root: &Path,
+    targets: &[Target],
+    parent: &ContractType,
+    actors: bool,
+    packs: &[PropertyPack],
+    base: &ContractBuilder,
+    namespace: &mut Namespace,
+    suite: &mut GeneratedSuite,
 ) -> Result<Vec<Contract>> {
-    // Determine the number of parents to generate
-    let count = match contract_type {
-        ContractType::Handler => args.nb_handlers,
-        ContractType::Property => args.nb_properties,
-        _ => {
-            return Err(anyhow::anyhow!("Invalid contract type in generate_parents"));
-        }
+    let mut contracts = Vec::new();
+    let handlers_dir = suite.out.join(ContractType::Handler.directory_name());
+
+    for target in targets {
+        let name = format!("{}{}", ContractType::Handler.name(), target.name);
+
+        let handlers = target
+            .functions
+            .iter()
+            .map(|function| {
+                namespace.claim(
+                    &name,
+                    &target.handler_name(function),
+                    &function.param_types(),
+                )
+            })
+            .collect::<MedusaGenResult<Vec<_>>>()?;
+
+        let contract = base
+            .clone()
+            .with_type(&ContractType::Handler)
+            .with_parent(parent)
+            .with_name(name)
+            .with_imports(format!(
+                "{}{}",
+                ContractType::Handler.import_of(parent),
+                target.import(root, &handlers_dir)?
+            ))
+            .with_body(regions::wrap(
+                regions::HANDLERS,
+                "  ",
+                &target.handler_body(
+                    actors,
+                    packs.iter().any(|pack| pack.clamps_holders(target)),
+                    &handlers,
+                ),
+            ))
+            .build();
+
+        suite
+            .insert_contract(ContractType::Handler.directory_name(), &contract)
+            .context(format!("Failed to render handler for {}", target.name))?;
+
+        contracts.push(contract);
+    }
+
+    Ok(contracts)
+}
+
+/// create one properties contract per property pack, checking the pack target instance, with the
+/// fuzzer property `prefix`
+fn create_property_packs(
+    packs: &[PropertyPack],
+    targets: &[Target],
+    actors: bool,
+    prefix: &str,
+    base: &ContractBuilder,
+    suite: &mut GeneratedSuite,
+) -> Result<Vec<Contract>> {
+    let mut contracts = Vec::new();
+
+    for pack in packs {
+        let contract = base
+            .clone()
+            .with_type(&ContractType::Property)
+            .with_name(format!("{}{}", ContractType::Property.name(), pack.name()))
+            .with_body(pack.body(targets, actors, prefix)?)
+            .build();
+
+        suite
+            .insert_contract(ContractType::Property.directory_name(), &contract)
+            .context(format!("Failed to render property pack {}", pack.name()))?;
+
+        contracts.push(contract);
+    }
+
+    Ok(contracts)
+}
+
+/// Create the Setup imports, target and actors declarations, deployments and helpers, `out` being
+/// relative to the project `root`
+fn parse_setup(
+    root: &Path,
+    targets: &[Target],
+    actors: Option<u8>,
+    out: &Path,
+) -> Result<(String, String)> {
+    if targets.is_empty() && actors.is_none() {
+        return Ok((String::new(), String::new()));
+    }
+
+    let imports = targets
+        .iter()
+        .map(|target| target.import(root, out))
+        .collect::<Result<String>>()?;
+
+    let mut declarations = targets
+        .iter()
+        .map(|target| target.declaration())
+        .collect::<String>();
+
+    let mut deployments = targets
+        .iter()
+        .map(|target| target.deployment())
+        .collect::<String>();
+
+    if let Some(count) = actors {
+        declarations.push_str(actors::declaration());
+        deployments.push_str(&actors::deployment(count));
+    }
+
+    // the clamping helper is only used by the target handlers
+    let helpers = if targets.is_empty() {
+        ""
+    } else {
+        abi::clamp_helper()
     };
 
-    // Use the helper function to generate the contracts
-    create_contracts(&contract_type, count, path)
+    Ok((
+        imports,
+        format!(
+            "{}\n  constructor() {{\n{}  }}\n{}{}",
+            declarations,
+            deployments,
+            if helpers.is_empty() { "" } else { "\n" },
+            helpers
+        ),
+    ))
 }
 
-/// Move the content of a temp folder to the fuzz test folder
-fn move_temp_contents(temp_dir: &TempDir, overwrite: bool) -> Result<()> {
-    let path = Path::new("./test/invariants/fuzz");
-    if path.exists() {
-        if !overwrite {
-            return Err(anyhow::anyhow!(
-                "Fuzz test folder already exists, did you mean --overwrite ?"
-            ));
-        }
+/// The contract the handlers inherit from, the hierarchy being Setup, then Actors when enabled,
+/// then Ghosts when a target has tracked getters
+fn handler_parent(spec: &SuiteSpec, targets: &[Target]) -> ContractType {
+    if targets.iter().any(|target| !target.getters.is_empty()) {
+        ContractType::Ghosts
+    } else {
+        ghosts_parent(spec)
+    }
+}
+
+/// The contract Ghosts inherits from: Actors when enabled, otherwise Setup
+fn ghosts_parent(spec: &SuiteSpec) -> ContractType {
+    match spec.actors {
+        Some(_) => ContractType::Actors,
+        None => ContractType::Setup,
+    }
+}
+
+/// The echidna.yaml config, sharing the campaign settings with medusa.json
+fn echidna_config(spec: &SuiteSpec) -> EchidnaConfig {
+    // both fuzzers write a coverage directory in their corpus
+    let corpus_dir = if spec.fuzzer.medusa() {
+        format!("{}/echidna", spec.campaign.corpus_dir())
     } else {
-        DirBuilder::new()
-            .recursive(true)
-            .create(path)
-            .context("Failed to create fuzz test folder")?;
+        spec.campaign.corpus_dir().to_string()
+    };
+
+    EchidnaConfig::new(&spec.campaign, spec.fuzzer.property_prefix(), corpus_dir)
+}
+
+/// Render the configs of the selected fuzzers in the suite
+fn render_fuzzer_configs(spec: &SuiteSpec, suite: &mut GeneratedSuite) -> Result<()> {
+    if spec.fuzzer.medusa() {
+        suite.configs.insert(
+            medusa::CONFIG_FILE_NAME.into(),
+            MedusaConfig::new(&spec.campaign).render()?,
+        );
+    }
+
+    if spec.fuzzer.echidna() {
+        suite.configs.insert(
+            echidna::CONFIG_FILE_NAME.into(),
+            echidna_config(spec).render_yaml()?,
+        );
+    }
+
+    Ok(())
+}
+
+/// The builder every contract starts from, with the compiler version and user templates
+fn base_builder(spec: &SuiteSpec) -> Result<ContractBuilder> {
+    let base = ContractBuilder::new()
+        .with_licence(spec.license.clone())
+        .with_solc(spec.solc.clone())?;
+
+    Ok(match &spec.templates {
+        Some(dir) => base.with_templates(
+            Templates::load(&spec.root.join(dir)).context("Failed to load templates")?,
+        ),
+        None => base,
+    })
+}
+
+/// Fill the options not passed on the command line with the medusa-gen.toml ones, then the
+/// foundry.toml ones, if any, both looked up in the project `root`
+fn resolve_args(args: &Args, root: &Path) -> Result<Args> {
+    let mut args = args.clone();
+
+    if let Some(config) = ProjectConfig::load(root).context("Failed to load project config")? {
+        args = args.with_project_config(&config);
+    }
+
+    if let Some(foundry) = FoundryConfig::load(root).context("Failed to load foundry config")? {
+        args = args.with_foundry_defaults(&foundry);
+    }
+
+    Ok(args)
+}
+
+/// The spec of the suite of `args`, in the project `root`
+fn suite_spec(args: &Args, root: &Path) -> SuiteSpec {
+    SuiteSpec {
+        root: root.to_path_buf(),
+        ..SuiteSpec::from(args)
+    }
+}
+
+/// Add a handler or property contract to the existing suite of the project `root`, and make its
+/// parent inherit it
+pub fn add_contract(
+    args: &Args,
+    root: &Path,
+    kind: ContractKind,
+    name: &str,
+) -> MedusaGenResult<()> {
+    let args = &resolve_args(args, root)?;
+    let out = root.join(args.out());
+
+    let contract_type = match kind {
+        ContractKind::Handler => ContractType::Handler,
+        ContractKind::Property => ContractType::Property,
+    };
+
+    validate_identifier(name).context(format!("Invalid {} name", contract_type.name()))?;
+
+    let dir = out.join(contract_type.directory_name());
+    let parent_name = format!("{}Parent", contract_type.name());
+    let parent_path = dir.join(format!("{}.t.sol", parent_name));
+
+    if !parent_path.exists() {
+        return Err(MedusaGenError::SuiteNotFound { path: parent_path });
     }
 
-    let options = CopyOptions {
-        overwrite,
-        skip_exist: !overwrite,
-        content_only: true,
-        ..Default::default()
+    // handlers inherit from the last of Ghosts and Actors the suite was generated with, if any
+    let exists =
+        |contract_type: ContractType| out.join(format!("{}.t.sol", contract_type.name())).exists();
+
+    let parent = match contract_type {
+        ContractType::Handler if exists(ContractType::Ghosts) => ContractType::Ghosts,
+        ContractType::Handler if exists(ContractType::Actors) => ContractType::Actors,
+        _ => contract_type.parent().unwrap_or(ContractType::Setup),
     };
 
-    copy(temp_dir.path(), "./test/invariants/fuzz", &options)
-        .context("Failed to copy temp directory contents")?;
+    let contract = base_builder(&suite_spec(args, root))?
+        .with_type(&contract_type)
+        .with_parent(&parent)
+        .with_name(format!("{}{}", contract_type.name(), name))
+        .build();
+
+    // update the parent first, so a failure leaves the suite untouched
+    let parent = std::fs::read_to_string(&parent_path)
+        .context(format!("Failed to read {}", parent_path.display()))?;
+    let updated = update::add_to_parent(&parent, &parent_name, &contract.name)
+        .context(format!("Failed to update {}", parent_name))?;
+
+    contract.write_rendered_contract(&dir).context(format!(
+        "Failed to write rendered {} contract",
+        contract.name
+    ))?;
+
+    std::fs::write(&parent_path, updated).map_err(|source| MedusaGenError::Io {
+        path: parent_path,
+        source,
+    })?;
 
     Ok(())
 }
 
-/// Generate and write the test suite
-pub fn generate_test_suite(args: &Args) -> Result<()> {
-    let temp_dir = TempDir::new().context("Failed creating temp dir")?; // will be deleted once dropped
+/// Write a Foundry test replaying a Medusa call sequence against the suite of the project `root`,
+/// then asserting `property` or every property of the suite
+pub fn generate_repro(
+    args: &Args,
+    root: &Path,
+    sequence: &Path,
+    property: Option<&str>,
+) -> MedusaGenResult<()> {
+    let args = &resolve_args(args, root)?;
+    let out = root.join(args.out());
+
+    let entry_point = out.join(format!("{}.t.sol", ContractType::EntryPoint.name()));
+
+    if !entry_point.exists() {
+        return Err(MedusaGenError::SuiteNotFound { path: entry_point });
+    }
+
+    let path = out.join(format!("{}.t.sol", ContractType::Repro.name()));
+
+    if path.exists() && !args.overwrite {
+        return Err(MedusaGenError::DestinationExists { path });
+    }
+
+    let calls = repro::load(&root.join(sequence))?;
+
+    let properties = match property {
+        Some(property) => vec![property.to_string()],
+        None => declared_functions(
+            &out.join(ContractType::Property.directory_name()),
+            args.fuzzer().property_prefix(),
+        )?,
+    };
+
+    // the cheatcodes are inherited from Actors, if the suite has one
+    let actors = out
+        .join(format!("{}.t.sol", ContractType::Actors.name()))
+        .exists();
+
+    let contract = base_builder(&suite_spec(args, root))?
+        .with_type(&ContractType::Repro)
+        .with_imports(format!(
+            "{}{}",
+            ContractType::Repro.import(),
+            if actors { "" } else { cheatcodes::interface() }
+        ))
+        .with_body(format!(
+            "{}{}",
+            if actors {
+                String::new()
+            } else {
+                format!("{}\n", cheatcodes::declaration())
+            },
+            repro::body(&calls, &properties)
+        ))
+        .build();
+
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|source| MedusaGenError::Io { path, source })?;
+    }
+
+    contract
+        .write_rendered_contract(&out)
+        .context("Failed to write rendered reproducer")?;
 
-    let handler_parents = generate_parents(
-        ContractType::Handler,
+    Ok(())
+}
+
+/// Generate a suite and the medusa-gen.toml capturing its options in the project `root`, the
+/// options being asked one by one when `interactive`. Returns false when the summary of the
+/// questions wasn't confirmed, nothing being written
+pub fn init(args: &Args, root: &Path, interactive: bool) -> MedusaGenResult<bool> {
+    init_with(
         args,
-        &temp_dir.path().join(ContractType::Handler.directory_name()),
+        root,
+        interactive.then(|| Wizard::new(std::io::stdin().lock(), std::io::stdout())),
     )
-    .context("Failed to generate handler parents")?;
+}
+
+fn init_with<R: BufRead, W: std::io::Write>(
+    args: &Args,
+    root: &Path,
+    wizard: Option<Wizard<R, W>>,
+) -> MedusaGenResult<bool> {
+    // the foundry.toml values are the defaults of the questions
+    let args = &resolve_args(args, root)?;
+
+    let config_path = root.join(config::CONFIG_FILE_NAME);
+
+    if config_path.exists() && !args.overwrite {
+        return Err(MedusaGenError::DestinationExists { path: config_path });
+    }
+
+    let args = match wizard {
+        Some(mut wizard) => {
+            let targets = wizard::discover_targets(root, args.src())
+                .context("Failed to discover the targets")?;
+
+            match wizard.run(args, &targets)? {
+                Some(args) => args,
+                None => return Ok(false),
+            }
+        }
+        None => args.clone(),
+    };
+
+    // the config is written first, so a failed generation can be retried from it
+    if !args.dry_run {
+        ProjectConfig::from(&args)
+            .write(root)
+            .context("Failed to write the project config")?;
+    }
+
+    generate_test_suite(&args, root)?;
+
+    Ok(true)
+}
+
+/// Check the inheritance tree, imports and pragmas of the existing suite of the project `root`,
+/// printing every issue found
+pub fn check_test_suite(args: &Args, root: &Path) -> MedusaGenResult<()> {
+    let args = &resolve_args(args, root)?;
+    let out = root.join(args.out());
+
+    let entry_point = out.join(format!("{}.t.sol", ContractType::EntryPoint.name()));
+
+    if !entry_point.exists() {
+        return Err(MedusaGenError::SuiteNotFound { path: entry_point });
+    }
+
+    let issues = check::check_suite(&out).context("Failed to check the test suite")?;
+
+    if issues.is_empty() {
+        println!("No issue found in {}", args.out().display());
+        return Ok(());
+    }
+
+    for issue in &issues {
+        println!("{}: {}", args.out().join(&issue.path).display(), issue.kind);
+    }
+
+    Err(MedusaGenError::CheckFailed {
+        count: issues.len(),
+    })
+}
+
+/// Render a suite in memory, without touching the fuzz test folder
+pub fn generate_suite(spec: &SuiteSpec) -> MedusaGenResult<GeneratedSuite> {
+    let mut suite = GeneratedSuite::new(&spec.out);
+
+    let mut targets = spec
+        .abis
+        .iter()
+        .map(|path| Target::from_file(&spec.root.join(path), &spec.src))
+        .collect::<Result<Vec<_>>>()
+        .context("Failed to load target ABI")?;
+
+    for target in &spec.targets {
+        targets.push(
+            Target::from_spec(target, &spec.root.join(&spec.artifacts))
+                .context("Failed to load target")?,
+        );
+    }
+
+    let packs = spec
+        .property_packs
+        .iter()
+        .map(|pack| PropertyPack::from_spec(pack))
+        .collect::<Result<Vec<_>>>()
+        .context("Failed to load property pack")?;
+
+    let base = base_builder(spec)?;
+
+    // the typed errors are returned as is, for callers to match them
+    let target_names: Vec<String> = targets.iter().map(|target| target.name.clone()).collect();
+    validate_names(&[target_names, spec.handlers.clone()].concat())?;
+    let pack_names: Vec<String> = packs.iter().map(|pack| pack.name()).collect();
+    validate_names(&[pack_names, spec.properties.clone()].concat())?;
+
+    // the hand-written functions of the existing handlers keep their name
+    let mut namespace = Namespace::new(spec.rename_collisions);
+    for (contract, function) in &spec.existing_functions {
+        namespace.declare(contract, function)?;
+    }
+
+    // named handlers come in addition to the targets ones
+    let mut handler_parents = create_target_handlers(
+        &spec.root,
+        &targets,
+        &handler_parent(spec, &targets),
+        spec.actors.is_some(),
+        &packs,
+        &base,
+        &mut namespace,
+        &mut suite,
+    )?;
+
+    handler_parents.extend(
+        create_contracts(
+            &ContractType::Handler,
+            &handler_parent(spec, &targets),
+            &spec.handlers,
+            &base,
+            &mut suite,
+        )
+        .context("Failed to generate handler parents")?,
+    );
+
+    // the functions added by the templates can't be renamed, the generated ones are claimed
+    for contract in &handler_parents {
+        let path = Path::new(ContractType::Handler.directory_name())
+            .join(format!("{}.t.sol", contract.name));
+        let source = regions::strip(&suite.contracts[&path])
+            .context(format!("Invalid regions in {}", contract.name))?;
+
+        for (contract, function) in check::contract_functions(&source) {
+            namespace.declare(&contract, &function)?;
+        }
+    }
 
-    let handler_child = ContractBuilder::new()
+    suite.renames = namespace.into_renames();
+
+    let handler_child = base
+        .clone()
         .with_type(&ContractType::Handler)
         .with_name(format!("{}Parent", &ContractType::Handler.name()))
         .with_imports(parse_child_imports(&handler_parents))
         .with_parents(parse_parents(&handler_parents))
         .build();
 
-    handler_child
-        .write_rendered_contract(&temp_dir.path().join(ContractType::Handler.directory_name()))
-        .context("Failed to write rendered handler child")?;
-
-    let properties_parents = generate_parents(
-        ContractType::Property,
-        args,
-        &temp_dir
-            .path()
-            .join(ContractType::Property.directory_name()),
+    suite
+        .insert_contract(ContractType::Handler.directory_name(), &handler_child)
+        .context("Failed to render handler child")?;
+
+    // named properties come in addition to the packs ones
+    let mut properties_parents = create_property_packs(
+        &packs,
+        &targets,
+        spec.actors.is_some(),
+        spec.fuzzer.property_prefix(),
+        &base,
+        &mut suite,
     )
-    .context("Failed to generate handler property")?;
+    .context("Failed to generate property packs")?;
+
+    properties_parents.extend(
+        create_contracts(
+            &ContractType::Property,
+            &ContractType::Handler,
+            &spec.properties,
+            &base,
+            &mut suite,
+        )
+        .context("Failed to generate handler property")?,
+    );
 
-    let property_child = ContractBuilder::new()
+    let property_child = base
+        .clone()
         .with_type(&ContractType::Property)
         .with_name(format!("{}Parent", &ContractType::Property.name()))
         .with_imports(parse_child_imports(&properties_parents))
         .with_parents(parse_parents(&properties_parents))
         .build();
 
-    property_child
-        .write_rendered_contract(
-            &temp_dir
-                .path()
-                .join(ContractType::Property.directory_name()),
-        )
-        .context("Failed to write rendered property child")?;
+    suite
+        .insert_contract(ContractType::Property.directory_name(), &property_child)
+        .context("Failed to render property child")?;
+
+    if spec.foundry {
+        let declared = |contract_type: ContractType, prefix: &str| {
+            functions_declared_in(
+                suite
+                    .contracts
+                    .iter()
+                    .filter(|(path, _)| path.starts_with(contract_type.directory_name()))
+                    .map(|(_, source)| source.as_str()),
+                prefix,
+            )
+        };
 
-    let entry_point = ContractBuilder::new()
-        .with_type(&ContractType::EntryPoint)
-        .build();
+        // the overloaded handlers are selected by signature, with the ABI types of the targets
+        let handlers = signatures_declared_in(
+            suite
+                .contracts
+                .iter()
+                .filter(|(path, _)| path.starts_with(ContractType::Handler.directory_name()))
+                .map(|(_, source)| source.as_str()),
+            abi::HANDLER_PREFIX,
+            &abi::canonical_types(&targets),
+        );
+        let properties = declared(ContractType::Property, spec.fuzzer.property_prefix());
+
+        let foundry_entry_point = base
+            .clone()
+            .with_type(&ContractType::FoundryEntryPoint)
+            .with_imports(format!(
+                "{}{}",
+                invariants::BASE_IMPORT,
+                ContractType::FoundryEntryPoint.import()
+            ))
+            .with_parents(format!(
+                "{}, {}",
+                ContractType::FoundryEntryPoint.import_name(),
+                invariants::BASE_NAME
+            ))
+            .with_body(invariants::body(
+                &handlers,
+                &properties,
+                spec.fuzzer.property_prefix(),
+            ))
+            .build();
+
+        suite
+            .insert_contract("", &foundry_entry_point)
+            .context("Failed to render foundry entry point")?;
+    }
+
+    let entry_point = base.clone().with_type(&ContractType::EntryPoint).build();
+
+    suite
+        .insert_contract("", &entry_point)
+        .context("Failed to render entry point")?;
+
+    if spec.actors.is_some() {
+        let actors = base
+            .clone()
+            .with_type(&ContractType::Actors)
+            .with_imports(format!(
+                "{}{}",
+                ContractType::Actors.import(),
+                cheatcodes::interface()
+            ))
+            .with_body(actors::body())
+            .build();
+
+        suite
+            .insert_contract("", &actors)
+            .context("Failed to render actors")?;
+    }
+
+    if handler_parent(spec, &targets) == ContractType::Ghosts {
+        let ghosts = base
+            .clone()
+            .with_type(&ContractType::Ghosts)
+            .with_parent(&ghosts_parent(spec))
+            .with_body(ghosts::body(&targets))
+            .build();
+
+        suite
+            .insert_contract("", &ghosts)
+            .context("Failed to render ghosts")?;
+    }
 
-    entry_point
-        .write_rendered_contract(temp_dir.path())
-        .context("Failed to write rendered entry point")?;
+    let (setup_imports, setup_body) = parse_setup(&spec.root, &targets, spec.actors, &spec.out)?;
 
-    let setup = ContractBuilder::new()
+    let setup = base
+        .clone()
         .with_type(&ContractType::Setup)
+        .with_imports(setup_imports)
+        .with_body(setup_body)
         .build();
 
-    setup
-        .write_rendered_contract(temp_dir.path())
-        .context("Failed to write rendered setup point")?;
+    suite
+        .insert_contract("", &setup)
+        .context("Failed to render setup point")?;
 
-    move_temp_contents(&temp_dir, args.overwrite).context("Failed to move temp contents")?;
+    render_fuzzer_configs(spec, &mut suite).context("Failed to render fuzzer config")?;
 
-    Ok(())
+    Ok(suite)
+}
+
+/// Generate and write the test suite in the project `root`
+pub fn generate_test_suite(args: &Args, root: &Path) -> MedusaGenResult<()> {
+    // options not passed on the command line default to the foundry.toml ones, if any
+    let args = &resolve_args(args, root)?;
+
+    let mut spec = suite_spec(args, root);
+
+    // the generated handlers must not collide with the hand-written functions of an existing
+    // suite, unless it is overwritten
+    if !args.overwrite {
+        spec.existing_functions = collisions::existing_functions(&root.join(args.out()))
+            .context("Failed to read the existing handlers")?;
+    }
+
+    let mut suite = generate_suite(&spec)?;
+
+    for rename in &suite.renames {
+        println!(
+            "Renamed {} to {} in {}",
+            rename.from, rename.to, rename.contract
+        );
+    }
+
+    // the fuzzer configs are often tuned by hand, they are only replaced on demand
+    if !args.overwrite_config {
+        for path in suite.keep_existing_configs(&DiskSink::new(root)) {
+            println!(
+                "Kept the existing {}, pass --overwrite-config to replace it",
+                path.display()
+            );
+        }
+    }
+
+    if args.dry_run {
+        print!(
+            "{}",
+            preview::preview(&suite, root).context("Failed to preview the test suite")?
+        );
+
+        return Ok(());
+    }
+
+    if args.merge {
+        let mut sink = MergeSink::new(root);
+        suite.write(&mut sink, true)?;
+        print!("{}", sink.summary());
+
+        return Ok(());
+    }
+
+    if args.regions {
+        let mut sink = RegionSink::new(root);
+        suite.write(&mut sink, true)?;
+        print!("{}", sink.summary());
+    } else {
+        suite.write(&mut DiskSink::new(root), args.overwrite)?;
+    }
+
+    // the generated files are the base of the next --merge
+    suite.write(&mut StateSink::new(root), true)
 }
 
 // TESTS //
@@ -182,7 +926,11 @@ pub fn generate_test_suite(args: &Args) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serial_test::serial;
+    use crate::cli::Fuzzer;
+    use clap::Parser;
+    use std::collections::BTreeSet;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
 
     #[test]
     fn test_parse_child_imports() {
@@ -192,6 +940,8 @@ mod tests {
             imports: "".to_string(),
             name: "HandlerA".to_string(),
             parents: "HandlersParent".to_string(),
+            body: "".to_string(),
+            template: None,
         }];
 
         assert_eq!(
@@ -209,6 +959,8 @@ mod tests {
                 imports: "".to_string(),
                 name: "HandlerA".to_string(),
                 parents: "HandlersParent".to_string(),
+                body: "".to_string(),
+                template: None,
             },
             Contract {
                 licence: "MIT".to_string(),
@@ -216,6 +968,8 @@ mod tests {
                 imports: "".to_string(),
                 name: "HandlerB".to_string(),
                 parents: "HandlersParent".to_string(),
+                body: "".to_string(),
+                template: None,
             },
         ];
 
@@ -239,6 +993,8 @@ mod tests {
             imports: "".to_string(),
             name: "HandlerA".to_string(),
             parents: "HandlersParent".to_string(),
+            body: "".to_string(),
+            template: None,
         }];
 
         assert_eq!(parse_parents(parents.as_ref()), "HandlerA");
@@ -253,6 +1009,8 @@ mod tests {
                 imports: "".to_string(),
                 name: "HandlerA".to_string(),
                 parents: "HandlersParent".to_string(),
+                body: "".to_string(),
+                template: None,
             },
             Contract {
                 licence: "MIT".to_string(),
@@ -260,6 +1018,8 @@ mod tests {
                 imports: "".to_string(),
                 name: "HandlerB".to_string(),
                 parents: "HandlersParent".to_string(),
+                body: "".to_string(),
+                template: None,
             },
         ];
 
@@ -272,16 +1032,48 @@ mod tests {
         assert_eq!(parse_parents(parents.as_ref()), "");
     }
 
+    #[test]
+    fn test_declared_functions() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        std::fs::write(
+            temp_dir.path().join("PropertiesA.t.sol"),
+            "contract PropertiesA {\n  function property_a() public returns (bool) {}\n  function _helper() internal {}\n}",
+        )?;
+        std::fs::write(
+            temp_dir.path().join("PropertiesB.t.sol"),
+            "contract PropertiesB {\n  function property_b() public view returns (bool) {}\n}",
+        )?;
+
+        assert_eq!(
+            declared_functions(temp_dir.path(), "property_")?,
+            vec![String::from("property_a"), String::from("property_b")]
+        );
+        assert!(declared_functions(&temp_dir.path().join("missing"), "property_")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_letter_suffix() {
+        assert_eq!(letter_suffix(0), "A");
+        assert_eq!(letter_suffix(25), "Z");
+        assert_eq!(letter_suffix(26), "AA");
+        assert_eq!(letter_suffix(27), "AB");
+        assert_eq!(letter_suffix(254), "IU");
+    }
+
     #[test]
     fn test_create_contracts() -> Result<()> {
-        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let mut suite = GeneratedSuite::new(Path::new("test/fuzz"));
         let contract_type = ContractType::Handler;
-        let count = 2;
+        let names = vec!["A".to_string(), "B".to_string()];
 
         let contracts = create_contracts(
             &contract_type,
-            count,
-            &temp_dir.path().join(contract_type.directory_name()),
+            &ContractType::Setup,
+            &names,
+            &ContractBuilder::new(),
+            &mut suite,
         )?;
 
         // Check that the correct number of contracts was created
@@ -291,202 +1083,1344 @@ mod tests {
         assert_eq!(contracts[0].name, "HandlersA");
         assert_eq!(contracts[1].name, "HandlersB");
 
-        // Verify the files were created in the correct directory
-        let handler_dir = temp_dir.path().join("handlers");
-        assert!(handler_dir.exists());
-        assert!(handler_dir.is_dir());
+        // Check that the contracts are rendered in the handlers directory
+        assert_eq!(
+            suite.contracts.keys().collect::<Vec<_>>(),
+            vec![
+                Path::new("handlers/HandlersA.t.sol"),
+                Path::new("handlers/HandlersB.t.sol")
+            ]
+        );
 
-        // Check that the contract files exist
-        assert!(handler_dir.join("HandlersA.t.sol").exists());
-        assert!(handler_dir.join("HandlersB.t.sol").exists());
-        assert!(!handler_dir.join("HandlersC.t.sol").exists());
+        // the same contract can't be rendered twice
+        assert!(create_contracts(
+            &contract_type,
+            &ContractType::Setup,
+            &names[..1],
+            &ContractBuilder::new(),
+            &mut suite,
+        )
+        .is_err());
 
         Ok(())
     }
 
     #[test]
     fn test_create_contracts_empty() -> Result<()> {
-        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let mut suite = GeneratedSuite::new(Path::new("test/fuzz"));
         let contract_type = ContractType::Handler;
-        let count = 0;
+        let names = vec![];
 
         let contracts = create_contracts(
             &contract_type,
-            count,
-            &temp_dir.path().join(contract_type.directory_name()),
+            &ContractType::Setup,
+            &names,
+            &ContractBuilder::new(),
+            &mut suite,
         )?;
 
         // Check that no contracts were created
         assert!(contracts.is_empty());
+        assert!(suite.contracts.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_suite() -> Result<()> {
+        let spec = SuiteBuilder::new()
+            .with_out(Path::new("test/fuzz"))
+            .with_handler("Vault")
+            .with_property("Solvency")
+            .build();
+
+        let suite = generate_suite(&spec)?;
 
-        // Verify the directory was still created
-        let handler_dir = temp_dir.path().join("handlers");
-        assert!(handler_dir.exists());
-        assert!(handler_dir.is_dir());
+        assert_eq!(
+            suite.files().map(|(path, _)| path).collect::<Vec<_>>(),
+            vec![
+                PathBuf::from("test/fuzz/FuzzTest.t.sol"),
+                PathBuf::from("test/fuzz/Setup.t.sol"),
+                PathBuf::from("test/fuzz/handlers/HandlersParent.t.sol"),
+                PathBuf::from("test/fuzz/handlers/HandlersVault.t.sol"),
+                PathBuf::from("test/fuzz/properties/PropertiesParent.t.sol"),
+                PathBuf::from("test/fuzz/properties/PropertiesSolvency.t.sol"),
+                PathBuf::from("medusa.json"),
+            ]
+        );
+        assert!(suite
+            .get(Path::new("test/fuzz/handlers/HandlersParent.t.sol"))
+            .unwrap()
+            .contains("contract HandlersParent is HandlersVault"));
+
+        let mut sink = MemorySink::new();
+        suite.write(&mut sink, false)?;
+
+        assert_eq!(sink.files.len(), 7);
+        assert_eq!(
+            sink.get(Path::new("test/fuzz/Setup.t.sol")),
+            suite.get(Path::new("test/fuzz/Setup.t.sol"))
+        );
 
         Ok(())
     }
 
     #[test]
-    fn test_generate_parents() -> Result<()> {
+    fn test_generate_suite_out_of_root() -> Result<()> {
         let temp_dir = TempDir::new().context("Failed to create temp dir")?;
-        let args = Args {
-            overwrite: true,
-            solc: "0.8.23".to_string(),
-            nb_handlers: 2,
-            nb_properties: 1,
-        };
-
-        // Test Handler parents
-        let handler_parents = generate_parents(
-            ContractType::Handler,
-            &args,
-            &temp_dir.path().join(ContractType::Handler.directory_name()),
+        let root = temp_dir.path().join("vault");
+        std::fs::create_dir(&root)?;
+        std::fs::write(
+            root.join("Vault.json"),
+            r#"[{"type": "function", "name": "deposit", "inputs": [], "stateMutability": "nonpayable"}]"#,
         )?;
 
-        assert_eq!(handler_parents.len(), 2);
-        assert_eq!(handler_parents[0].name, "HandlersA");
-        assert_eq!(handler_parents[1].name, "HandlersB");
-
-        // Test Property parents
-        let property_parents = generate_parents(
-            ContractType::Property,
-            &args,
-            &temp_dir
-                .path()
-                .join(ContractType::Property.directory_name()),
+        // --out ../fuzz, next to the project root
+        let suite = generate_suite(
+            &SuiteBuilder::new()
+                .with_root(&root)
+                .with_out(Path::new("../fuzz"))
+                .with_abi(Path::new("Vault.json"))
+                .build(),
         )?;
 
-        assert_eq!(property_parents.len(), 1);
-        assert_eq!(property_parents[0].name, "PropertiesA");
+        assert!(suite
+            .get(Path::new("../fuzz/Setup.t.sol"))
+            .unwrap()
+            .contains("import {Vault} from '../vault/src/Vault.sol';"));
+        assert!(suite
+            .get(Path::new("../fuzz/handlers/HandlersVault.t.sol"))
+            .unwrap()
+            .contains("import {Vault} from '../../vault/src/Vault.sol';"));
 
         Ok(())
     }
 
     #[test]
-    fn test_generate_parents_invalid_type() {
-        let temp_dir = TempDir::new().unwrap();
-        let args = Args {
-            overwrite: true,
-            solc: "0.8.23".to_string(),
-            nb_handlers: 2,
-            nb_properties: 1,
-        };
+    fn test_generate_test_suite_foundry_overloads() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(
+            root.join("Nft.json"),
+            r#"[
+                {"type": "function", "name": "safeTransferFrom", "inputs": [{"name": "id", "type": "uint256"}], "stateMutability": "nonpayable"},
+                {"type": "function", "name": "safeTransferFrom", "inputs": [{"name": "id", "type": "uint256"}, {"name": "data", "type": "bytes"}], "stateMutability": "nonpayable"},
+                {"type": "function", "name": "swap", "inputs": [{"name": "params", "type": "tuple[]", "internalType": "struct Nft.Params[]", "components": [{"name": "id", "type": "uint256"}, {"name": "to", "type": "address"}]}], "stateMutability": "nonpayable"},
+                {"type": "function", "name": "swap", "inputs": [{"name": "params", "type": "tuple[]", "internalType": "struct Nft.Params[]", "components": [{"name": "id", "type": "uint256"}, {"name": "to", "type": "address"}]}, {"name": "token", "type": "address", "internalType": "contract IERC20"}], "stateMutability": "nonpayable"},
+                {"type": "function", "name": "burn", "inputs": [{"name": "id", "type": "uint256"}], "stateMutability": "nonpayable"}
+            ]"#,
+        )?;
 
-        let result = generate_parents(ContractType::Setup, &args, temp_dir.path());
+        let args = Args::parse_from([
+            "medusa-gen",
+            "--abi",
+            "Nft.json",
+            "--actors",
+            "2",
+            "--foundry",
+        ]);
+
+        generate_test_suite(&args, root)?;
+
+        // the overloads are selected by signature, with the ABI types
+        let entry_point =
+            std::fs::read_to_string(root.join("test/invariants/fuzz/FuzzTestFoundry.t.sol"))?;
+        assert!(entry_point.contains(
+            "    bytes4[] memory _selectors = new bytes4[](5);
+    _selectors[0] = bytes4(keccak256(\"handler_nft_safeTransferFrom(uint256,uint256)\"));
+    _selectors[1] = bytes4(keccak256(\"handler_nft_safeTransferFrom(uint256,bytes,uint256)\"));
+    _selectors[2] = bytes4(keccak256(\"handler_nft_swap((uint256,address)[],uint256)\"));
+    _selectors[3] = bytes4(keccak256(\"handler_nft_swap((uint256,address)[],address,uint256)\"));
+    _selectors[4] = this.handler_nft_burn.selector;
+"
+        ));
+        assert!(!entry_point.contains("this.handler_nft_safeTransferFrom.selector"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_suite_foundry_hierarchy() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let abi = temp_dir.path().join("Token.json");
+        std::fs::write(
+            &abi,
+            r#"[{"type": "function", "name": "transfer", "inputs": [{"name": "to", "type": "address"}], "stateMutability": "nonpayable"}, {"type": "function", "name": "balanceOf", "inputs": [{"name": "account", "type": "address"}], "stateMutability": "view"}]"#,
+        )?;
+
+        let suite = generate_suite(
+            &SuiteBuilder::new()
+                .with_abi(&abi)
+                .with_property_pack("erc20:Token")
+                .with_actors(2)
+                .with_foundry(true)
+                .build(),
+        )?;
+
+        // no solc nor forge-std here, the hierarchy is rebuilt from the contract headers
+        let parents: BTreeMap<String, (String, Vec<String>)> = suite
+            .files()
+            .filter_map(|(_, source)| {
+                let header = source.lines().find(|line| line.starts_with("contract "))?;
+                let header = header["contract ".len()..].trim_end_matches('{').trim();
+                let (name, parents) = header.split_once(" is ").unwrap_or((header, ""));
+
+                Some((
+                    name.trim().to_string(),
+                    (
+                        source.to_string(),
+                        parents.split(',').map(|p| p.trim().to_string()).collect(),
+                    ),
+                ))
+            })
+            .collect();
+
+        let mut hierarchy = BTreeSet::new();
+        let mut stack = vec![String::from("FuzzTestFoundry")];
+
+        while let Some(name) = stack.pop() {
+            if hierarchy.insert(name.clone()) {
+                if let Some((_, parents)) = parents.get(&name) {
+                    stack.extend(parents.iter().filter(|p| !p.is_empty()).cloned());
+                }
+            }
+        }
+
+        // StdInvariant is the only forge-std contract, it doesn't declare vm (unlike Test, through
+        // CommonBase), so the vm of Actors is declared once
+        let external: Vec<&String> = hierarchy
+            .iter()
+            .filter(|name| !parents.contains_key(*name))
+            .collect();
+        assert_eq!(external, vec!["StdInvariant"]);
+
+        let declaring_vm: Vec<&String> = hierarchy
+            .iter()
+            .filter(|name| {
+                parents
+                    .get(*name)
+                    .is_some_and(|(source, _)| source.contains(" vm = "))
+            })
+            .collect();
+        assert_eq!(declaring_vm, vec!["Actors"]);
+
+        for (path, source) in suite.files() {
+            assert!(!source.contains("forge-std/Test.sol"), "{}", path.display());
+            assert!(!source.contains("forge-std/Base.sol"), "{}", path.display());
+            assert_eq!(
+                source.contains("forge-std"),
+                path.ends_with("FuzzTestFoundry.t.sol"),
+                "{}",
+                path.display()
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_suite_collisions() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let abi = temp_dir.path().join("Nft.json");
+        std::fs::write(
+            &abi,
+            r#"[{"type": "function", "name": "safeTransferFrom", "inputs": [{"name": "id", "type": "uint256"}], "stateMutability": "nonpayable"}, {"type": "function", "name": "safeTransferFrom", "inputs": [{"name": "id", "type": "uint256"}, {"name": "data", "type": "bytes"}], "stateMutability": "nonpayable"}]"#,
+        )?;
+
+        let builder = SuiteBuilder::new().with_abi(&abi).with_handler("A");
+
+        // the overloads of a target are overloaded in its handler
+        let suite = generate_suite(&builder.clone().build())?;
+
+        let handler = suite
+            .get(Path::new("test/invariants/fuzz/handlers/HandlersNft.t.sol"))
+            .unwrap();
+        assert!(handler.contains("function handler_nft_safeTransferFrom(uint256 _id) public {"));
+        assert!(handler.contains(
+            "function handler_nft_safeTransferFrom(uint256 _id, bytes memory _data) public {"
+        ));
+        assert!(suite.renames.is_empty());
+
+        // a function of another handler collides
+        let builder = builder
+            .with_existing_function("HandlersA", "handler_nft_safeTransferFrom")
+            .with_existing_function("HandlersA", "handler_nft_safeTransferFrom_2");
+
+        assert!(matches!(
+            generate_suite(&builder.clone().build()),
+            Err(MedusaGenError::FunctionCollision { function, first, second })
+                if function == "handler_nft_safeTransferFrom"
+                    && first == "HandlersA"
+                    && second == "HandlersNft"
+        ));
+
+        let suite = generate_suite(&builder.clone().with_rename_collisions(true).build())?;
+
+        let handler = suite
+            .get(Path::new("test/invariants/fuzz/handlers/HandlersNft.t.sol"))
+            .unwrap();
+        assert!(handler.contains("function handler_nft_safeTransferFrom_3(uint256 _id) public {"));
+        assert!(handler.contains(
+            "function handler_nft_safeTransferFrom_3(uint256 _id, bytes memory _data) public {"
+        ));
+        assert_eq!(suite.renames.len(), 2);
+        assert!(suite
+            .renames
+            .iter()
+            .all(|rename| rename.contract == "HandlersNft"
+                && rename.from == "handler_nft_safeTransferFrom"
+                && rename.to == "handler_nft_safeTransferFrom_3"));
+
+        // the functions of the templates can't be renamed
+        let templates = temp_dir.path().join("templates");
+        std::fs::create_dir(&templates)?;
+        std::fs::write(
+            templates.join("handler.sol"),
+            "contract {{ name }} is {{ parents }} {\n  function _bound() internal {}\n}",
+        )?;
+
+        assert!(matches!(
+            generate_suite(
+                &SuiteBuilder::new()
+                    .with_handler("A")
+                    .with_handler("B")
+                    .with_templates(&templates)
+                    .with_rename_collisions(true)
+                    .build()
+            ),
+            Err(MedusaGenError::FunctionCollision { function, first, second })
+                if function == "_bound" && first == "HandlersA" && second == "HandlersB"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        let args = Args::parse_from([
+            "medusa-gen",
+            "-o",
+            "--out",
+            "./test/invariants/fuzz",
+            "--solc",
+            "0.8.23",
+            "-p",
+            "1",
+        ]);
+
+        let result = generate_test_suite(&args, root);
+        assert!(result.is_ok());
+
+        let fuzz_dir = root.join("test/invariants/fuzz");
+        assert!(fuzz_dir.join("handlers/HandlersA.t.sol").exists());
+        assert!(fuzz_dir.join("handlers/HandlersB.t.sol").exists());
+        assert!(fuzz_dir.join("handlers/HandlersParent.t.sol").exists());
+        assert!(fuzz_dir.join("properties/PropertiesA.t.sol").exists());
+        assert!(fuzz_dir.join("properties/PropertiesParent.t.sol").exists());
+        assert!(fuzz_dir.join("Setup.t.sol").exists());
+        assert!(fuzz_dir.join("FuzzTest.t.sol").exists());
+        assert!(root.join("medusa.json").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_existing_medusa_config() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(root.join("medusa.json"), "{}")?;
+
+        let args = Args::parse_from([
+            "medusa-gen",
+            "--out",
+            "./test/invariants/fuzz",
+            "--solc",
+            "0.8.23",
+            "-p",
+            "1",
+        ]);
+
+        // the hand-tuned config is kept, the suite is generated
+        generate_test_suite(&args, root)?;
+
+        assert!(root.join("test/invariants/fuzz/FuzzTest.t.sol").exists());
+        assert_eq!(std::fs::read_to_string(root.join("medusa.json"))?, "{}");
+
+        // the config is only replaced on demand, the suite is left as is
+        let args = Args::parse_from(["medusa-gen", "-p", "1", "--overwrite-config"]);
+        assert!(matches!(
+            generate_test_suite(&args, root),
+            Err(MedusaGenError::DestinationExists { .. })
+        ));
+
+        generate_test_suite(
+            &Args {
+                overwrite: true,
+                ..args
+            },
+            root,
+        )?;
+        assert!(std::fs::read_to_string(root.join("medusa.json"))?.contains("\"targetContracts\""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_custom_out() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        let args = Args::parse_from([
+            "medusa-gen",
+            "--out",
+            "packages/vault/test/fuzz",
+            "--solc",
+            "0.8.23",
+            "-n",
+            "1",
+            "-p",
+            "1",
+        ]);
+
+        let result = generate_test_suite(&args, root);
+        assert!(result.is_ok());
+
+        let fuzz_dir = root.join("packages/vault/test/fuzz");
+        assert!(fuzz_dir.join("handlers/HandlersA.t.sol").exists());
+        assert!(fuzz_dir.join("properties/PropertiesParent.t.sol").exists());
+        assert!(fuzz_dir.join("FuzzTest.t.sol").exists());
+        assert!(!root.join("test/invariants/fuzz").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_from_abi() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(
+            root.join("Vault.json"),
+            r#"[{"type": "function", "name": "deposit", "inputs": [{"name": "assets", "type": "uint256"}], "stateMutability": "nonpayable"}]"#,
+        )?;
+
+        let args = Args::parse_from([
+            "medusa-gen",
+            "--abi",
+            "Vault.json",
+            "--out",
+            "./test/invariants/fuzz",
+            "--solc",
+            "0.8.23",
+            "-p",
+            "1",
+        ]);
+
+        let result = generate_test_suite(&args, root);
+        assert!(result.is_ok());
+
+        let fuzz_dir = root.join("test/invariants/fuzz");
+        assert!(fuzz_dir.join("handlers/HandlersVault.t.sol").exists());
+        assert!(!fuzz_dir.join("handlers/HandlersA.t.sol").exists());
+
+        let handler = std::fs::read_to_string(fuzz_dir.join("handlers/HandlersVault.t.sol"))?;
+        assert!(handler.contains("import {Vault} from '../../../../src/Vault.sol';"));
+        assert!(handler.contains("function handler_vault_deposit(uint256 _assets) public {"));
+        assert!(handler.contains("vault.deposit(_assets);"));
+
+        let parent = std::fs::read_to_string(fuzz_dir.join("handlers/HandlersParent.t.sol"))?;
+        assert!(parent.contains("contract HandlersParent is HandlersVault {"));
+
+        let setup = std::fs::read_to_string(fuzz_dir.join("Setup.t.sol"))?;
+        assert!(setup.contains("import {Vault} from '../../../src/Vault.sol';"));
+        assert!(setup.contains("Vault internal vault;"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_solc() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        let args = Args::parse_from([
+            "medusa-gen",
+            "--out",
+            "./test/invariants/fuzz",
+            "--solc",
+            ">=0.8.0 <0.9.0",
+            "-n",
+            "1",
+            "-p",
+            "1",
+        ]);
+
+        generate_test_suite(&args, root)?;
+
+        let fuzz_dir = root.join("test/invariants/fuzz");
+        for file in [
+            "handlers/HandlersA.t.sol",
+            "handlers/HandlersParent.t.sol",
+            "properties/PropertiesA.t.sol",
+            "properties/PropertiesParent.t.sol",
+            "Setup.t.sol",
+            "FuzzTest.t.sol",
+        ] {
+            assert!(std::fs::read_to_string(fuzz_dir.join(file))?
+                .contains("pragma solidity >=0.8.0 <0.9.0;"));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_invalid_solc() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        let args = Args::parse_from([
+            "medusa-gen",
+            "--out",
+            "./test/invariants/fuzz",
+            "--solc",
+            "latest",
+            "-n",
+            "1",
+            "-p",
+            "1",
+        ]);
+
+        let result = generate_test_suite(&args, root);
 
         assert!(result.is_err());
+        assert!(!root.join("test/invariants/fuzz").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_foundry_defaults() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(
+            root.join("foundry.toml"),
+            "[profile.default]\nsolc_version = \"0.8.26\"\nsrc = \"contracts\"\ntest = \"tests\"\n",
+        )?;
+        std::fs::write(
+            root.join("Vault.json"),
+            r#"[{"type": "function", "name": "deposit", "inputs": [], "stateMutability": "nonpayable"}]"#,
+        )?;
+
+        let args = Args::parse_from(["medusa-gen", "--abi", "Vault.json", "-n", "1", "-p", "1"]);
+
+        generate_test_suite(&args, root)?;
+
+        let setup = std::fs::read_to_string(root.join("tests/invariants/fuzz/Setup.t.sol"))?;
+        assert!(setup.contains("pragma solidity 0.8.26;"));
+        assert!(setup.contains("import {Vault} from '../../../contracts/Vault.sol';"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_named() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        let args = Args::parse_from([
+            "medusa-gen",
+            "--handlers",
+            "Vault,Oracle",
+            "--properties",
+            "Solvency",
+        ]);
+
+        generate_test_suite(&args, root)?;
+
+        let fuzz_dir = root.join("test/invariants/fuzz");
+        assert!(fuzz_dir.join("handlers/HandlersVault.t.sol").exists());
+        assert!(fuzz_dir.join("handlers/HandlersOracle.t.sol").exists());
+        assert!(!fuzz_dir.join("handlers/HandlersA.t.sol").exists());
+        assert!(fuzz_dir
+            .join("properties/PropertiesSolvency.t.sol")
+            .exists());
+        assert!(!fuzz_dir.join("properties/PropertiesA.t.sol").exists());
+
+        let parent = std::fs::read_to_string(fuzz_dir.join("handlers/HandlersParent.t.sol"))?;
+        assert!(parent.contains("contract HandlersParent is HandlersVault, HandlersOracle {"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_invalid_names() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        let mut args = Args::parse_from(["medusa-gen", "--handlers", "Vault,Vault"]);
+
+        let result = generate_test_suite(&args, root);
+        assert!(matches!(
+            result.unwrap_err(),
+            MedusaGenError::DuplicateName(name) if name == "Vault"
+        ));
+
+        args.handlers = vec![];
+        args.properties = vec!["contract".to_string()];
+
+        let result = generate_test_suite(&args, root);
         assert_eq!(
             result.unwrap_err().to_string(),
-            "Invalid contract type in generate_parents"
+            "contract is a reserved Solidity keyword"
         );
+        assert!(!root.join("test/invariants/fuzz").exists());
+
+        Ok(())
     }
 
-    // All the move_temp_contents are in serial to avoid having race conditions
     #[test]
-    #[serial]
-    fn test_move_temp_contents() -> Result<()> {
+    fn test_add_contract() -> Result<()> {
         let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
 
-        // Create a test file in temp directory
-        let test_file = temp_dir.path().join("test.txt");
-        std::fs::write(&test_file, "test content")?;
+        let args = Args::parse_from(["medusa-gen", "-p", "1"]);
 
-        let result = move_temp_contents(&temp_dir, true);
-        assert!(result.is_ok());
+        generate_test_suite(&args, root)?;
+
+        // hand-written code in an existing handler must be preserved
+        let fuzz_dir = root.join("test/invariants/fuzz");
+        let handler_a = fuzz_dir.join("handlers/HandlersA.t.sol");
+        std::fs::write(&handler_a, "// hand-written")?;
+
+        add_contract(&args, root, ContractKind::Handler, "Foo")?;
+        add_contract(&args, root, ContractKind::Property, "Bar")?;
 
-        let dest_file = Path::new("./test/invariants/fuzz/test.txt");
-        assert!(dest_file.exists());
-        assert_eq!(std::fs::read_to_string(dest_file)?, "test content");
+        assert!(fuzz_dir.join("handlers/HandlersFoo.t.sol").exists());
+        assert!(fuzz_dir.join("properties/PropertiesBar.t.sol").exists());
+        assert_eq!(std::fs::read_to_string(&handler_a)?, "// hand-written");
+
+        let parent = std::fs::read_to_string(fuzz_dir.join("handlers/HandlersParent.t.sol"))?;
+        assert!(parent.contains("import { HandlersFoo } from './HandlersFoo.t.sol';"));
+        assert!(parent.contains("contract HandlersParent is HandlersA, HandlersB, HandlersFoo {"));
+
+        let parent = std::fs::read_to_string(fuzz_dir.join("properties/PropertiesParent.t.sol"))?;
+        assert!(parent.contains("contract PropertiesParent is PropertiesA, PropertiesBar {"));
+
+        // adding twice fails without touching the parent
+        assert!(add_contract(&args, root, ContractKind::Handler, "Foo").is_err());
+        assert!(add_contract(&args, root, ContractKind::Handler, "contract").is_err());
 
-        std::fs::remove_dir_all("./test/invariants/fuzz")?;
         Ok(())
     }
 
     #[test]
-    #[serial]
-    fn test_move_temp_contents_no_overwrite() -> Result<()> {
+    fn test_generate_test_suite_merge() -> Result<()> {
         let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
 
-        std::fs::create_dir_all("./test/invariants/fuzz")?;
+        generate_test_suite(&Args::parse_from(["medusa-gen", "--handlers", "A"]), root)?;
 
-        let result = move_temp_contents(&temp_dir, false);
+        let fuzz_dir = root.join("test/invariants/fuzz");
+        let handler_a = fuzz_dir.join("handlers/HandlersA.t.sol");
+        let edited = std::fs::read_to_string(&handler_a)?.replace(
+            "contract HandlersA is Setup {\n",
+            "contract HandlersA is Setup {\n  function handler_a() public {}\n",
+        );
+        std::fs::write(&handler_a, &edited)?;
 
-        assert!(result.is_err());
+        // a new handler is added to the parent, the edited one is kept
+        generate_test_suite(
+            &Args::parse_from(["medusa-gen", "--handlers", "A,B", "--merge"]),
+            root,
+        )?;
+
+        assert_eq!(std::fs::read_to_string(&handler_a)?, edited);
+        assert!(fuzz_dir.join("handlers/HandlersB.t.sol").exists());
+        assert!(
+            std::fs::read_to_string(fuzz_dir.join("handlers/HandlersParent.t.sol"))?
+                .contains("contract HandlersParent is HandlersA, HandlersB {")
+        );
+        assert!(root
+            .join(merge::STATE_DIR)
+            .join("test/invariants/fuzz/handlers/HandlersA.t.sol")
+            .exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_existing_functions() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(
+            root.join("Token.json"),
+            r#"[{"type": "function", "name": "transfer", "inputs": [{"name": "to", "type": "address"}], "stateMutability": "nonpayable"}]"#,
+        )?;
+
+        generate_test_suite(&Args::parse_from(["medusa-gen", "--handlers", "A"]), root)?;
+
+        let handler_a = root.join("test/invariants/fuzz/handlers/HandlersA.t.sol");
+        let edited = std::fs::read_to_string(&handler_a)?.replace(
+            "// medusa-gen:end parents\n",
+            "// medusa-gen:end parents\n  function handler_token_transfer() public {}\n",
+        );
+        std::fs::write(&handler_a, &edited)?;
+
+        // the hand-written function is found without --merge or --regions
+        let args = Args::parse_from(["medusa-gen", "--handlers", "A", "--abi", "Token.json"]);
+        assert!(matches!(
+            generate_test_suite(&Args { dry_run: true, ..args.clone() }, root),
+            Err(MedusaGenError::FunctionCollision { function, first, second })
+                if function == "handler_token_transfer"
+                    && first == "HandlersA"
+                    && second == "HandlersToken"
+        ));
+
+        // the overwritten handlers lose their functions
+        generate_test_suite(
+            &Args {
+                overwrite: true,
+                ..args
+            },
+            root,
+        )?;
+        assert!(!std::fs::read_to_string(&handler_a)?.contains("handler_token_transfer"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_merge_absolute_out() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+        let fuzz_dir = root.join("fuzz");
+        let out = fuzz_dir.to_str().unwrap();
+
+        generate_test_suite(
+            &Args::parse_from(["medusa-gen", "--handlers", "A", "--out", out]),
+            root,
+        )?;
+
+        let handler_a = fuzz_dir.join("handlers/HandlersA.t.sol");
+        let edited = std::fs::read_to_string(&handler_a)?.replace(
+            "contract HandlersA is Setup {\n",
+            "contract HandlersA is Setup {\n  function handler_a() public {}\n",
+        );
+        std::fs::write(&handler_a, &edited)?;
+
+        // the merge base is recorded in the state directory, not over the suite
+        assert!(root
+            .join(merge::STATE_DIR)
+            .join("fuzz/handlers/HandlersA.t.sol")
+            .exists());
+
+        generate_test_suite(
+            &Args::parse_from(["medusa-gen", "--handlers", "A,B", "--out", out, "--merge"]),
+            root,
+        )?;
+
+        assert_eq!(std::fs::read_to_string(&handler_a)?, edited);
+        assert!(fuzz_dir.join("handlers/HandlersB.t.sol").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_regions_absolute_out() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+        let fuzz_dir = root.join("fuzz");
+        let out = fuzz_dir.to_str().unwrap();
+
+        generate_test_suite(
+            &Args::parse_from(["medusa-gen", "--handlers", "A", "--out", out]),
+            root,
+        )?;
+
+        let handler_a = fuzz_dir.join("handlers/HandlersA.t.sol");
+        let edited = format!(
+            "{}\n// hand-written notes\n",
+            std::fs::read_to_string(&handler_a)?
+        );
+        std::fs::write(&handler_a, &edited)?;
+
+        // recording the generated files doesn't overwrite the edits out of the regions
+        generate_test_suite(
+            &Args::parse_from(["medusa-gen", "--handlers", "A,B", "--out", out, "--regions"]),
+            root,
+        )?;
+
+        assert_eq!(std::fs::read_to_string(&handler_a)?, edited);
+        assert!(
+            std::fs::read_to_string(fuzz_dir.join("handlers/HandlersParent.t.sol"))?
+                .contains("contract HandlersParent is HandlersA, HandlersB {")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_contract_no_suite() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        let args = Args::parse_from(["medusa-gen", "add", "handler", "Foo"]);
+
+        let result = add_contract(&args, root, ContractKind::Handler, "Foo");
+        assert!(matches!(
+            result.unwrap_err(),
+            MedusaGenError::SuiteNotFound { path }
+                if path == root.join("./test/invariants/fuzz/handlers/HandlersParent.t.sol")
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_test_suite() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        let args = Args::parse_from(["medusa-gen", "--handlers", "A,B", "--actors", "2"]);
+
+        assert!(matches!(
+            check_test_suite(&args, root),
+            Err(MedusaGenError::SuiteNotFound { .. })
+        ));
+
+        generate_test_suite(&args, root)?;
+        add_contract(&args, root, ContractKind::Handler, "Foo")?;
+        check_test_suite(&args, root)?;
+
+        // a handler not inherited by HandlersParent
+        std::fs::write(
+            root.join("test/invariants/fuzz/handlers/HandlersBar.t.sol"),
+            "pragma solidity 0.8.23;\n\ncontract HandlersBar {\n}",
+        )?;
+
+        assert!(matches!(
+            check_test_suite(&args, root),
+            Err(MedusaGenError::CheckFailed { count: 1 })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_init() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        let args = Args::parse_from(["medusa-gen", "--handlers", "Vault", "init"]);
+        assert!(init(&args, root, false)?);
+
+        assert!(root
+            .join("test/invariants/fuzz/handlers/HandlersVault.t.sol")
+            .exists());
         assert_eq!(
-            result.unwrap_err().to_string(),
-            "Fuzz test folder already exists, did you mean --overwrite ?"
+            ProjectConfig::load(root)?.unwrap().handlers,
+            vec![String::from("Vault")]
         );
 
-        std::fs::remove_dir_all("./test/invariants/fuzz")?;
+        // the config isn't overwritten without --overwrite
+        assert!(matches!(
+            init(&args, root, false),
+            Err(MedusaGenError::DestinationExists { path }) if path == root.join(config::CONFIG_FILE_NAME)
+        ));
+
         Ok(())
     }
 
     #[test]
-    #[serial]
-    fn test_move_temp_contents_new_directory() -> Result<()> {
+    fn test_init_failed_generation() -> Result<()> {
         let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
 
-        // source directory with test file
-        let source_dir = temp_dir.path().join("source");
-        std::fs::create_dir(&source_dir)?;
-        let test_file = source_dir.join("test.txt");
-        std::fs::write(&test_file, "test content")?;
+        // the target artifact is missing, the config is written anyway to fix and retry from
+        let args = Args::parse_from(["medusa-gen", "--target", "src/Vault.sol:Vault", "init"]);
+        assert!(init(&args, root, false).is_err());
 
-        // TempDir for source that will be moved
-        let source_temp =
-            TempDir::new_in(&source_dir).context("Failed to create source temp dir")?;
-        std::fs::write(source_temp.path().join("test.txt"), "test content")?;
+        assert_eq!(
+            ProjectConfig::load(root)?.unwrap().targets,
+            vec![String::from("src/Vault.sol:Vault")]
+        );
 
-        // Set current directory to temp_dir
-        let original_dir = std::env::current_dir()?;
-        std::env::set_current_dir(&temp_dir)?;
+        Ok(())
+    }
 
-        // Test moving to non-existent directory
-        let result = move_temp_contents(&source_temp, false);
-        if let Err(ref e) = result {
-            println!("Error: {:#}", e);
-        }
-        assert!(result.is_ok());
+    #[test]
+    fn test_init_interactive() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::create_dir(root.join("src"))?;
+        std::fs::write(root.join("src/Vault.sol"), "contract Vault {}")?;
 
-        let fuzz_dir = Path::new("./test/invariants/fuzz");
-        assert!(fuzz_dir.exists());
-        assert!(fuzz_dir.is_dir());
-        let dest_file = fuzz_dir.join("test.txt");
-        assert!(dest_file.exists());
-        assert_eq!(std::fs::read_to_string(dest_file)?, "test content");
+        // no contract to fuzz, the numbered properties are kept
+        let answers = "0.8.26\n\n\nVault\n\n2\nechidna\ny\n";
+        let mut output = Vec::new();
+
+        assert!(init_with(
+            &Args::parse_from(["medusa-gen", "init", "--interactive"]),
+            root,
+            Some(Wizard::new(answers.as_bytes(), &mut output)),
+        )?);
+
+        assert!(String::from_utf8(output)?.contains("  1) src/Vault.sol:Vault\n"));
+        assert!(root.join("test/invariants/fuzz/Actors.t.sol").exists());
+        assert!(root.join(echidna::CONFIG_FILE_NAME).exists());
+        assert_eq!(
+            ProjectConfig::load(root)?.unwrap(),
+            ProjectConfig {
+                solc: Some(String::from("0.8.26")),
+                out: Some(PathBuf::from("./test/invariants/fuzz")),
+                handlers: vec![String::from("Vault")],
+                properties: vec![String::from("A"), String::from("B")],
+                actors: Some(2),
+                fuzzer: Some(Fuzzer::Echidna),
+                ..Default::default()
+            }
+        );
 
-        std::env::set_current_dir(original_dir)?;
         Ok(())
     }
 
     #[test]
-    #[serial]
-    fn test_generate_test_suite() -> Result<()> {
+    fn test_init_interactive_aborted() -> Result<()> {
         let temp_dir = TempDir::new().context("Failed to create temp dir")?;
-        let original_dir = std::env::current_dir()?;
-        std::env::set_current_dir(&temp_dir)?;
+        let root = temp_dir.path();
 
-        let args = Args {
-            overwrite: true,
-            solc: "0.8.23".to_string(),
-            nb_handlers: 2,
-            nb_properties: 1,
-        };
+        // every default, then the summary isn't confirmed
+        let answers = "\n\n\n\n0\n\nn\n";
+
+        assert!(!init_with(
+            &Args::parse_from(["medusa-gen", "init", "--interactive"]),
+            root,
+            Some(Wizard::new(answers.as_bytes(), Vec::new())),
+        )?);
+        assert_eq!(std::fs::read_dir(root)?.count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_dry_run() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(root.join("medusa.json"), "{}")?;
+
+        let result = generate_test_suite(&Args::parse_from(["medusa-gen", "--dry-run"]), root);
 
-        let result = generate_test_suite(&args);
         assert!(result.is_ok());
+        assert!(!root.join("test/invariants/fuzz").exists());
+        assert_eq!(std::fs::read_to_string(root.join("medusa.json"))?, "{}");
 
-        let fuzz_dir = Path::new("test/invariants/fuzz");
-        assert!(fuzz_dir.join("handlers/HandlersA.t.sol").exists());
-        assert!(fuzz_dir.join("handlers/HandlersB.t.sol").exists());
-        assert!(fuzz_dir.join("handlers/HandlersParent.t.sol").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_templates() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::create_dir(root.join("templates"))?;
+        std::fs::write(
+            root.join("templates/handler.sol"),
+            "pragma solidity {{ solc }};\n/// @title {{ name }}\ncontract {{ name }} is {{ parents }} {}",
+        )?;
+
+        let args = Args::parse_from(["medusa-gen", "-n", "1", "--templates", "templates"]);
+
+        generate_test_suite(&args, root)?;
+
+        let fuzz_dir = root.join("test/invariants/fuzz");
+        assert_eq!(
+            std::fs::read_to_string(fuzz_dir.join("handlers/HandlersA.t.sol"))?,
+            "pragma solidity 0.8.23;\n/// @title HandlersA\ncontract HandlersA is Setup {}"
+        );
+
+        // types without a template use the embedded one
+        assert!(std::fs::read_to_string(fuzz_dir.join("Setup.t.sol"))?
+            .starts_with("// SPDX-License-Identifier: MIT"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_project_config() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(
+            root.join("medusa-gen.toml"),
+            "solc = \"0.8.20\"\nout = \"fuzz\"\nhandlers = [\"Vault\"]\nproperties = [\"Solvency\"]\nlicense = \"GPL-3.0\"\n",
+        )?;
+
+        // the solc and number of properties passed on the command line win over the file
+        let args = Args::parse_from(["medusa-gen", "--solc", "0.8.26", "-p", "1"]);
+
+        generate_test_suite(&args, root)?;
+
+        let fuzz_dir = root.join("fuzz");
+        let handler = std::fs::read_to_string(fuzz_dir.join("handlers/HandlersVault.t.sol"))?;
+        assert!(handler.starts_with("// SPDX-License-Identifier: GPL-3.0\npragma solidity 0.8.26;"));
         assert!(fuzz_dir.join("properties/PropertiesA.t.sol").exists());
-        assert!(fuzz_dir.join("properties/PropertiesParent.t.sol").exists());
-        assert!(fuzz_dir.join("Setup.t.sol").exists());
-        assert!(fuzz_dir.join("FuzzTest.t.sol").exists());
+        assert!(!fuzz_dir
+            .join("properties/PropertiesSolvency.t.sol")
+            .exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_project_config_overrides() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(
+            root.join("medusa-gen.toml"),
+            "out = \"fuzz\"\nfoundry = true\nartifacts = \"build\"\ntargets = [\"src/Vault.sol:Vault\"]\nworkers = 4\nseq_len = 50\n",
+        )?;
+        std::fs::create_dir_all(root.join("build/Vault.sol"))?;
+        std::fs::write(
+            root.join("build/Vault.sol/Vault.json"),
+            r#"{"abi": [{"type": "function", "name": "deposit", "inputs": [], "stateMutability": "nonpayable"}]}"#,
+        )?;
+
+        // the configured booleans can be turned off, and the campaign settings overridden
+        let args = Args::parse_from(["medusa-gen", "--no-foundry", "--workers", "2"]);
+
+        generate_test_suite(&args, root)?;
+
+        let fuzz_dir = root.join("fuzz");
+        assert!(fuzz_dir.join("handlers/HandlersVault.t.sol").exists());
+        assert!(!fuzz_dir.join("FuzzTestFoundry.t.sol").exists());
+
+        let medusa: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(root.join("medusa.json"))?)?;
+        assert_eq!(medusa["fuzzing"]["workers"], 2);
+        assert_eq!(medusa["fuzzing"]["callSequenceLength"], 50);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_targets() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::create_dir_all(root.join("out/Vault.sol"))?;
+        std::fs::write(
+            root.join("out/Vault.sol/Vault.json"),
+            r#"{"abi": [
+                {"type": "constructor", "inputs": [{"name": "_asset", "type": "address", "internalType": "contract IERC20"}, {"name": "_fee", "type": "uint256", "internalType": "uint256"}], "stateMutability": "nonpayable"},
+                {"type": "function", "name": "deposit", "inputs": [], "stateMutability": "nonpayable"}
+            ]}"#,
+        )?;
+
+        let args = Args::parse_from(["medusa-gen", "--target", "src/vaults/Vault.sol:Vault"]);
+
+        generate_test_suite(&args, root)?;
+
+        let fuzz_dir = root.join("test/invariants/fuzz");
+        let setup = std::fs::read_to_string(fuzz_dir.join("Setup.t.sol"))?;
+        assert!(setup.contains("import {Vault, IERC20} from '../../../src/vaults/Vault.sol';"));
+        assert!(setup.contains("  Vault internal vault;\n\n  constructor() {\n"));
+        assert!(setup.contains("    vault = new Vault(IERC20(address(0)), 0);\n  }"));
+        assert!(fuzz_dir.join("handlers/HandlersVault.t.sol").exists());
+
+        // missing artifact
+        let args = Args::parse_from(["medusa-gen", "--target", "src/Oracle.sol:Oracle", "-o"]);
+        assert!(generate_test_suite(&args, root).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_actors() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(
+            root.join("Vault.json"),
+            r#"[{"type": "function", "name": "deposit", "inputs": [], "stateMutability": "nonpayable"}]"#,
+        )?;
+
+        let args = Args::parse_from(["medusa-gen", "--abi", "Vault.json", "--actors", "3"]);
+
+        generate_test_suite(&args, root)?;
+
+        let fuzz_dir = root.join("test/invariants/fuzz");
+        let setup = std::fs::read_to_string(fuzz_dir.join("Setup.t.sol"))?;
+        assert!(setup.contains("  address[] internal actors;\n"));
+        assert!(setup.contains("for (uint256 _i; _i < 3; _i++) {"));
+
+        let actors = std::fs::read_to_string(fuzz_dir.join("Actors.t.sol"))?;
+        assert!(actors.contains("import {Setup} from './Setup.t.sol';"));
+        assert!(actors.contains("contract Actors is Setup {"));
+        assert!(actors.contains("modifier useActor(uint256 _actorSeed) {"));
+
+        let handler = std::fs::read_to_string(fuzz_dir.join("handlers/HandlersVault.t.sol"))?;
+        assert!(handler.contains("import {Actors} from '../Actors.t.sol';"));
+        assert!(handler.contains("contract HandlersVault is Actors {"));
+        assert!(handler.contains(
+            "function handler_vault_deposit(uint256 _actorSeed) public useActor(_actorSeed) {"
+        ));
+
+        // handlers added later inherit from Actors too
+        add_contract(&args, root, ContractKind::Handler, "Foo")?;
+        let added = std::fs::read_to_string(fuzz_dir.join("handlers/HandlersFoo.t.sol"))?;
+        assert!(added.contains("contract HandlersFoo is Actors {"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_ghosts() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(
+            root.join("Token.json"),
+            r#"[
+                {"type": "function", "name": "transfer", "inputs": [{"name": "to", "type": "address"}, {"name": "amount", "type": "uint256"}], "stateMutability": "nonpayable"},
+                {"type": "function", "name": "balanceOf", "inputs": [{"name": "account", "type": "address"}], "stateMutability": "view"},
+                {"type": "function", "name": "totalSupply", "inputs": [], "stateMutability": "view"}
+            ]"#,
+        )?;
+
+        let args = Args::parse_from(["medusa-gen", "--abi", "Token.json", "--actors", "2"]);
+
+        generate_test_suite(&args, root)?;
+
+        let fuzz_dir = root.join("test/invariants/fuzz");
+        let ghosts = std::fs::read_to_string(fuzz_dir.join("Ghosts.t.sol"))?;
+        assert!(ghosts.contains("import {Actors} from './Actors.t.sol';"));
+        assert!(ghosts.contains("contract Ghosts is Actors {"));
+        assert!(
+            ghosts.contains("mapping(address => uint256) internal ghost_token_balanceOfBefore;")
+        );
+        assert!(ghosts.contains("ghost_token_totalSupplyAfter = token.totalSupply();"));
+
+        let handler = std::fs::read_to_string(fuzz_dir.join("handlers/HandlersToken.t.sol"))?;
+        assert!(handler.contains("contract HandlersToken is Ghosts {"));
+        assert!(handler.contains(
+            "    _beforeToken(currentActor);\n    token.transfer(_to, _amount);\n    _afterToken(currentActor);\n"
+        ));
+
+        // handlers added later inherit from Ghosts too
+        add_contract(&args, root, ContractKind::Handler, "Foo")?;
+        let added = std::fs::read_to_string(fuzz_dir.join("handlers/HandlersFoo.t.sol"))?;
+        assert!(added.contains("contract HandlersFoo is Ghosts {"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_property_packs() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(
+            root.join("Token.json"),
+            r#"[{"type": "function", "name": "transfer", "inputs": [{"name": "to", "type": "address"}, {"name": "amount", "type": "uint256"}], "stateMutability": "nonpayable"}]"#,
+        )?;
+
+        let args = Args::parse_from([
+            "medusa-gen",
+            "--abi",
+            "Token.json",
+            "--property-pack",
+            "erc20:Token",
+            "--properties",
+            "Solvency",
+        ]);
+
+        generate_test_suite(&args, root)?;
+
+        let properties_dir = root.join("test/invariants/fuzz/properties");
+        let pack = std::fs::read_to_string(properties_dir.join("PropertiesERC20Token.t.sol"))?;
+        assert!(pack.contains("contract PropertiesERC20Token is HandlersParent {"));
+        assert!(pack.contains("return _sum == token.totalSupply();"));
+        assert!(properties_dir.join("PropertiesSolvency.t.sol").exists());
+
+        // the transfers stay between the accounts summed by the pack
+        let handler = std::fs::read_to_string(
+            root.join("test/invariants/fuzz/handlers/HandlersToken.t.sol"),
+        )?;
+        assert!(handler.contains("    _to = address(this);\n    token.transfer(_to, _amount);"));
+        assert!(!properties_dir.join("PropertiesA.t.sol").exists());
+
+        let parent = std::fs::read_to_string(properties_dir.join("PropertiesParent.t.sol"))?;
+        assert!(parent.contains("is PropertiesERC20Token, PropertiesSolvency {"));
+
+        // the pack target must be deployed in Setup
+        let args = Args::parse_from(["medusa-gen", "--property-pack", "erc721:Nft", "-o"]);
+        assert!(generate_test_suite(&args, root).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_repro() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(
+            root.join("sequence.json"),
+            r#"[{"call": {"from": "0x0000000000000000000000000000000000010000", "value": "0x0", "data": "0x", "dataAbiValues": {"methodSignature": "handler_a(uint256)", "inputValues": ["1"]}}, "blockNumberDelay": 1, "blockTimestampDelay": 1}]"#,
+        )?;
+
+        // the suite must exist
+        let args = Args::parse_from(["medusa-gen", "repro", "sequence.json"]);
+        assert!(generate_repro(&args, root, Path::new("sequence.json"), None).is_err());
+
+        generate_test_suite(
+            &Args::parse_from(["medusa-gen", "--properties", "Solvency"]),
+            root,
+        )?;
+        std::fs::write(
+            root.join("test/invariants/fuzz/properties/PropertiesSolvency.t.sol"),
+            "contract PropertiesSolvency {\n  function property_solvent() public returns (bool) {}\n}",
+        )?;
+
+        generate_repro(&args, root, Path::new("sequence.json"), None)?;
+
+        let repro = std::fs::read_to_string(root.join("test/invariants/fuzz/Repro.t.sol"))?;
+        assert!(repro.contains("import {FuzzTest} from './FuzzTest.t.sol';"));
+        assert!(repro.contains("interface IVm {"));
+        assert!(repro.contains("contract Repro is FuzzTest {"));
+        assert!(repro.contains("  IVm internal constant vm = IVm("));
+        assert!(
+            repro.contains("    vm.prank(address(0x10000));\n    this.handler_a(uint256(1));\n")
+        );
+        assert!(repro.contains("    require(property_solvent(), \"property_solvent failed\");\n"));
+
+        // an existing reproducer is kept, unless overwritten
+        assert!(generate_repro(
+            &args,
+            root,
+            Path::new("sequence.json"),
+            Some("property_other")
+        )
+        .is_err());
+
+        let args = Args::parse_from(["medusa-gen", "repro", "sequence.json", "-o"]);
+        generate_repro(
+            &args,
+            root,
+            Path::new("sequence.json"),
+            Some("property_other"),
+        )?;
+        let repro = std::fs::read_to_string(root.join("test/invariants/fuzz/Repro.t.sol"))?;
+        assert!(repro.contains("require(property_other(), "));
+        assert!(!repro.contains("property_solvent"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_fuzzer() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(
+            root.join("Token.json"),
+            r#"[{"type": "function", "name": "transfer", "inputs": [], "stateMutability": "nonpayable"}]"#,
+        )?;
+
+        let args = Args::parse_from([
+            "medusa-gen",
+            "--fuzzer",
+            "echidna",
+            "--abi",
+            "Token.json",
+            "--property-pack",
+            "erc20:Token",
+        ]);
+
+        generate_test_suite(&args, root)?;
+
+        assert!(!root.join(medusa::CONFIG_FILE_NAME).exists());
+        let config = std::fs::read_to_string(root.join(echidna::CONFIG_FILE_NAME))?;
+        assert!(config.contains("prefix: \"echidna_\"\n"));
+        assert!(config.contains("corpusDir: \"corpus\"\n"));
+
+        let pack = std::fs::read_to_string(
+            root.join("test/invariants/fuzz/properties/PropertiesERC20Token.t.sol"),
+        )?;
+        assert!(pack.contains("function echidna_erc20_transferKeepsTotalSupply()"));
+
+        // both fuzzers test the medusa prefix, the existing echidna.yaml isn't overwritten
+        let args = Args::parse_from(["medusa-gen", "--fuzzer", "both", "--properties", "A"]);
+        assert!(generate_test_suite(&args, root).is_err());
+        assert!(!root.join(medusa::CONFIG_FILE_NAME).exists());
+
+        let args = Args::parse_from([
+            "medusa-gen",
+            "--fuzzer",
+            "both",
+            "--properties",
+            "A",
+            "-o",
+            "--overwrite-config",
+        ]);
+        generate_test_suite(&args, root)?;
+
+        assert!(root.join(medusa::CONFIG_FILE_NAME).exists());
+        let config = std::fs::read_to_string(root.join(echidna::CONFIG_FILE_NAME))?;
+        assert!(config.contains("prefix: \"property_\"\n"));
+        assert!(config.contains("corpusDir: \"corpus/echidna\"\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_test_suite_foundry() -> Result<()> {
+        let temp_dir = TempDir::new().context("Failed to create temp dir")?;
+        let root = temp_dir.path();
+
+        std::fs::write(
+            root.join("Token.json"),
+            r#"[{"type": "function", "name": "transfer", "inputs": [{"name": "to", "type": "address"}], "stateMutability": "nonpayable"}]"#,
+        )?;
+
+        let args = Args::parse_from([
+            "medusa-gen",
+            "--abi",
+            "Token.json",
+            "--property-pack",
+            "erc721:Token",
+            "--actors",
+            "2",
+            "--foundry",
+        ]);
+
+        generate_test_suite(&args, root)?;
+
+        let fuzz_dir = root.join("test/invariants/fuzz");
+        let entry_point = std::fs::read_to_string(fuzz_dir.join("FuzzTestFoundry.t.sol"))?;
+        assert!(entry_point.contains("import {StdInvariant} from 'forge-std/StdInvariant.sol';\nimport {PropertiesParent} from './properties/PropertiesParent.t.sol';\n"));
+        assert!(
+            entry_point.contains("contract FuzzTestFoundry is PropertiesParent, StdInvariant {")
+        );
+        assert!(entry_point.contains("    _selectors[0] = this.handler_token_transfer.selector;\n"));
+        assert!(entry_point.contains("  function invariant_erc721_ownerHasBalance() public {"));
+
+        // the suite run by Medusa doesn't depend on forge-std
+        let actors = std::fs::read_to_string(fuzz_dir.join("Actors.t.sol"))?;
+        assert!(!actors.contains("forge-std"));
+        assert!(actors.contains("contract Actors is Setup {"));
+        assert!(actors.contains("  IVm internal constant vm = IVm("));
 
-        std::env::set_current_dir(original_dir)?;
         Ok(())
     }
 }
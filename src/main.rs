@@ -1,11 +1,25 @@
 use anyhow::Result;
 use clap::Parser;
-use medusa_gen::cli::Args;
+use medusa_gen::cli::{Args, Command};
+use std::path::Path;
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    let root = Path::new(".");
 
-    medusa_gen::generate_test_suite(&args)?;
+    match &args.command {
+        Some(Command::Add { kind, name }) => medusa_gen::add_contract(&args, root, *kind, name)?,
+        Some(Command::Repro { sequence, property }) => {
+            medusa_gen::generate_repro(&args, root, sequence, property.as_deref())?
+        }
+        Some(Command::Check) => medusa_gen::check_test_suite(&args, root)?,
+        Some(Command::Init { interactive }) => {
+            if !medusa_gen::init(&args, root, *interactive)? {
+                println!("Nothing was written");
+            }
+        }
+        None => medusa_gen::generate_test_suite(&args, root)?,
+    }
 
     Ok(())
 }
@@ -0,0 +1,237 @@
+use crate::cli::MedusaArgs;
+use crate::types::ContractType;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// Prefix of the property functions, as tested by Medusa
+pub const PROPERTY_PREFIX: &str = "property_";
+
+/// Name of the generated Medusa config file, written in the project root
+pub const CONFIG_FILE_NAME: &str = "medusa.json";
+
+/// The medusa.json content, only the commonly tuned fields are exposed, others have Medusa defaults
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MedusaConfig {
+    pub fuzzing: Fuzzing,
+    pub compilation: Compilation,
+    pub logging: Logging,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Fuzzing {
+    pub workers: u8,
+    pub worker_reset_limit: u32,
+    pub timeout: u64,
+    pub test_limit: u64,
+    pub shrink_limit: u32,
+    pub call_sequence_length: u32,
+    pub corpus_directory: String,
+    pub coverage_enabled: bool,
+    pub target_contracts: Vec<String>,
+    pub target_contracts_balances: Vec<String>,
+    pub deployer_address: String,
+    pub sender_addresses: Vec<String>,
+    pub block_number_delay_max: u64,
+    pub block_timestamp_delay_max: u64,
+    pub block_gas_limit: u64,
+    pub transaction_gas_limit: u64,
+    pub testing: Testing,
+    pub chain_config: ChainConfig,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Testing {
+    pub stop_on_failed_test: bool,
+    pub stop_on_failed_contract_matching: bool,
+    pub stop_on_no_tests: bool,
+    pub test_all_contracts: bool,
+    pub trace_all: bool,
+    pub assertion_testing: AssertionTesting,
+    pub property_testing: PrefixedTesting,
+    pub optimization_testing: PrefixedTesting,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertionTesting {
+    pub enabled: bool,
+    pub test_view_methods: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PrefixedTesting {
+    pub enabled: bool,
+    pub test_prefixes: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainConfig {
+    pub code_size_check_disabled: bool,
+    pub cheat_codes: CheatCodes,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CheatCodes {
+    pub cheat_codes_enabled: bool,
+    #[serde(rename = "enableFFI")]
+    pub enable_ffi: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Compilation {
+    pub platform: String,
+    pub platform_config: PlatformConfig,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlatformConfig {
+    pub target: String,
+    pub solc_version: String,
+    pub export_directory: String,
+    pub args: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Logging {
+    pub level: String,
+    pub log_directory: String,
+    pub no_color: bool,
+}
+
+impl MedusaConfig {
+    /// Build the config targeting the generated entry point, using crytic-compile with Foundry
+    pub fn new(args: &MedusaArgs) -> MedusaConfig {
+        MedusaConfig {
+            fuzzing: Fuzzing {
+                workers: args.workers(),
+                worker_reset_limit: 50,
+                timeout: args.timeout(),
+                test_limit: args.test_limit(),
+                shrink_limit: 5000,
+                call_sequence_length: args.seq_len(),
+                corpus_directory: args.corpus_dir().to_string(),
+                coverage_enabled: true,
+                target_contracts: vec![ContractType::EntryPoint.name().to_string()],
+                target_contracts_balances: vec![],
+                deployer_address: String::from("0x30000"),
+                sender_addresses: vec![
+                    String::from("0x10000"),
+                    String::from("0x20000"),
+                    String::from("0x30000"),
+                ],
+                block_number_delay_max: 60480,
+                block_timestamp_delay_max: 604800,
+                block_gas_limit: 125000000,
+                transaction_gas_limit: 12500000,
+                testing: Testing {
+                    stop_on_failed_test: true,
+                    stop_on_failed_contract_matching: false,
+                    stop_on_no_tests: true,
+                    test_all_contracts: false,
+                    trace_all: false,
+                    assertion_testing: AssertionTesting {
+                        enabled: true,
+                        test_view_methods: false,
+                    },
+                    property_testing: PrefixedTesting {
+                        enabled: true,
+                        test_prefixes: vec![PROPERTY_PREFIX.to_string()],
+                    },
+                    optimization_testing: PrefixedTesting {
+                        enabled: false,
+                        test_prefixes: vec![String::from("optimize_")],
+                    },
+                },
+                chain_config: ChainConfig {
+                    code_size_check_disabled: true,
+                    cheat_codes: CheatCodes {
+                        cheat_codes_enabled: true,
+                        enable_ffi: false,
+                    },
+                },
+            },
+            compilation: Compilation {
+                platform: String::from("crytic-compile"),
+                platform_config: PlatformConfig {
+                    target: String::from("."),
+                    solc_version: String::new(),
+                    export_directory: String::new(),
+                    args: vec![String::from("--foundry-compile-all")],
+                },
+            },
+            logging: Logging {
+                level: String::from("info"),
+                log_directory: String::new(),
+                no_color: false,
+            },
+        }
+    }
+
+    /// Serialize the config as pretty json
+    pub fn render(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map(|rendered| rendered + "\n")
+            .context("Failed to serialize medusa config")
+    }
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_new() {
+        let args = MedusaArgs {
+            workers: Some(4),
+            test_limit: Some(1000),
+            ..Default::default()
+        };
+
+        let config = MedusaConfig::new(&args);
+
+        assert_eq!(config.fuzzing.workers, 4);
+        assert_eq!(config.fuzzing.test_limit, 1000);
+        assert_eq!(config.fuzzing.target_contracts, vec!["FuzzTest"]);
+        assert_eq!(
+            config.fuzzing.testing.property_testing.test_prefixes,
+            vec!["property_"]
+        );
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let json: Value =
+            serde_json::from_str(&MedusaConfig::new(&MedusaArgs::default()).render()?)?;
+
+        assert_eq!(json["fuzzing"]["workers"], 10);
+        assert_eq!(json["fuzzing"]["corpusDirectory"], "corpus");
+        assert_eq!(json["fuzzing"]["targetContracts"][0], "FuzzTest");
+        assert_eq!(
+            json["fuzzing"]["testing"]["propertyTesting"]["testPrefixes"][0],
+            "property_"
+        );
+        assert_eq!(
+            json["fuzzing"]["chainConfig"]["cheatCodes"]["enableFFI"],
+            false
+        );
+        assert_eq!(json["compilation"]["platform"], "crytic-compile");
+        assert_eq!(
+            json["compilation"]["platformConfig"]["args"][0],
+            "--foundry-compile-all"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+use crate::error::{MedusaGenError, MedusaGenResult};
+use crate::sink::{DiskSink, Sink};
+use crate::types::normalize_path;
+
+use anyhow::anyhow;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Directory of the project root where the last generated version of every file is recorded, as
+/// the base of the next merge
+pub const STATE_DIR: &str = ".medusa-gen";
+
+/// The path in `STATE_DIR` recording the generated version of `path` (relative to `root`, or
+/// absolute). A path out of the root is recorded by its absolute path without the leading "/"
+/// (eg "/tmp/fuzz/Setup.t.sol" gives ".medusa-gen/tmp/fuzz/Setup.t.sol")
+pub fn state_path(root: &Path, path: &Path) -> MedusaGenResult<PathBuf> {
+    let absolute = |path: &Path| {
+        std::path::absolute(path)
+            .map(|absolute| normalize_path(&absolute))
+            .map_err(|source| MedusaGenError::Io {
+                path: path.to_path_buf(),
+                source,
+            })
+    };
+
+    let root = absolute(root)?;
+    let path = absolute(&root.join(path))?;
+
+    let relative: PathBuf = match path.strip_prefix(&root) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => path
+            .components()
+            .skip_while(|component| matches!(component, Component::Prefix(_) | Component::RootDir))
+            .collect(),
+    };
+
+    if relative.as_os_str().is_empty()
+        || relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return Err(MedusaGenError::Other(anyhow!(
+            "Invalid generated file path {}",
+            path.display()
+        )));
+    }
+
+    Ok(Path::new(STATE_DIR).join(relative))
+}
+
+/// Record the generated files in the `STATE_DIR` of a project root, as the base of the next merge
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateSink {
+    root: PathBuf,
+}
+
+impl StateSink {
+    pub fn new(root: &Path) -> StateSink {
+        StateSink {
+            root: root.to_path_buf(),
+        }
+    }
+}
+
+impl Sink for StateSink {
+    fn exists(&self, path: &Path) -> bool {
+        state_path(&self.root, path).is_ok_and(|state| self.root.join(state).exists())
+    }
+
+    fn write(&mut self, path: &Path, content: &str) -> MedusaGenResult<()> {
+        let state = state_path(&self.root, path)?;
+        DiskSink::new(&self.root).write(&state, content)
+    }
+}
+
+/// What merging a regenerated file did
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergeStatus {
+    /// The file didn't exist
+    New,
+    /// Neither the template nor the file changed, or only the file did
+    Unchanged,
+    /// The template changes were applied on top of the file edits
+    Merged,
+    /// The template and the file changed the same lines, the file has conflict markers
+    Conflict,
+}
+
+impl MergeStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            MergeStatus::New => "new",
+            MergeStatus::Unchanged => "unchanged",
+            MergeStatus::Merged => "merged",
+            MergeStatus::Conflict => "conflict",
+        }
+    }
+}
+
+/// Write the files in a project root on disk, merging the changes since the last generation
+/// into the existing ones instead of replacing them
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeSink {
+    root: PathBuf,
+    report: Vec<(PathBuf, MergeStatus)>,
+}
+
+impl MergeSink {
+    pub fn new(root: &Path) -> MergeSink {
+        MergeSink {
+            root: root.to_path_buf(),
+            report: Vec::new(),
+        }
+    }
+
+    /// The status of every file written, in order
+    pub fn report(&self) -> &[(PathBuf, MergeStatus)] {
+        &self.report
+    }
+
+    /// The files with conflict markers
+    pub fn conflicts(&self) -> impl Iterator<Item = &Path> {
+        self.report
+            .iter()
+            .filter(|(_, status)| *status == MergeStatus::Conflict)
+            .map(|(path, _)| path.as_path())
+    }
+
+    /// Describe the files which were merged or have conflicts
+    pub fn summary(&self) -> String {
+        let mut output = String::new();
+
+        for (path, status) in &self.report {
+            if matches!(status, MergeStatus::Merged | MergeStatus::Conflict) {
+                let _ = writeln!(output, "{} ({})", path.display(), status.label());
+            }
+        }
+
+        if self.conflicts().next().is_some() {
+            let _ = writeln!(
+                output,
+                "\nResolve the conflict markers, the template version is below the \"=======\" line"
+            );
+        }
+
+        output
+    }
+
+    fn read(path: &Path) -> MedusaGenResult<Option<String>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        fs::read_to_string(path)
+            .map(Some)
+            .map_err(|source| MedusaGenError::Io {
+                path: path.to_path_buf(),
+                source,
+            })
+    }
+}
+
+impl Sink for MergeSink {
+    fn exists(&self, path: &Path) -> bool {
+        self.root.join(path).exists()
+    }
+
+    fn write(&mut self, path: &Path, content: &str) -> MedusaGenResult<()> {
+        let current = MergeSink::read(&self.root.join(path))?;
+        let base = MergeSink::read(&self.root.join(state_path(&self.root, path)?))?;
+
+        let (merged, status) = match current {
+            None => (content.to_string(), MergeStatus::New),
+            Some(current) if current == content => (current, MergeStatus::Unchanged),
+            // without a recorded base, the whole file is a conflict
+            Some(current) => {
+                match diffy::merge(base.as_deref().unwrap_or_default(), &current, content) {
+                    Ok(merged) if merged == current => (merged, MergeStatus::Unchanged),
+                    Ok(merged) => (merged, MergeStatus::Merged),
+                    Err(conflicted) => (conflicted, MergeStatus::Conflict),
+                }
+            }
+        };
+
+        if status != MergeStatus::Unchanged {
+            DiskSink::new(&self.root).write(path, &merged)?;
+        }
+
+        // the next merge starts from the template version, not the merged one
+        StateSink::new(&self.root).write(path, content)?;
+
+        self.report.push((path.to_path_buf(), status));
+
+        Ok(())
+    }
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_merge_sink() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = Path::new("handlers/HandlersA.t.sol");
+        let file = temp_dir.path().join(path);
+
+        let mut sink = MergeSink::new(temp_dir.path());
+        sink.write(path, "import A;\n\ncontract A {\n}\n")?;
+
+        // the user edits the body, the template adds an import
+        fs::write(&file, "import A;\n\ncontract A {\n  uint256 x;\n}\n")?;
+        sink.write(path, "import A;\nimport B;\n\ncontract A {\n}\n")?;
+
+        assert_eq!(
+            fs::read_to_string(&file)?,
+            "import A;\nimport B;\n\ncontract A {\n  uint256 x;\n}\n"
+        );
+
+        // the template changes the edited line
+        sink.write(
+            path,
+            "import A;\nimport B;\n\ncontract A {\n  uint256 y;\n}\n",
+        )?;
+
+        assert!(fs::read_to_string(&file)?.contains("<<<<<<< ours\n  uint256 x;\n"));
+        assert_eq!(
+            sink.report()
+                .iter()
+                .map(|(_, status)| *status)
+                .collect::<Vec<_>>(),
+            vec![MergeStatus::New, MergeStatus::Merged, MergeStatus::Conflict]
+        );
+        assert_eq!(sink.conflicts().collect::<Vec<_>>(), vec![path]);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(STATE_DIR).join(path))?,
+            "import A;\nimport B;\n\ncontract A {\n  uint256 y;\n}\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_state_path() -> anyhow::Result<()> {
+        let root = Path::new("/projects/vault");
+
+        assert_eq!(
+            state_path(root, Path::new("./test/fuzz/Setup.t.sol"))?,
+            Path::new(".medusa-gen/test/fuzz/Setup.t.sol")
+        );
+
+        // an absolute path in the root is recorded relative to it
+        assert_eq!(
+            state_path(root, Path::new("/projects/vault/fuzz/Setup.t.sol"))?,
+            Path::new(".medusa-gen/fuzz/Setup.t.sol")
+        );
+
+        // out of the root, the absolute path is recorded without its root
+        assert_eq!(
+            state_path(root, Path::new("../fuzz/Setup.t.sol"))?,
+            Path::new(".medusa-gen/projects/fuzz/Setup.t.sol")
+        );
+        assert_eq!(
+            state_path(root, Path::new("/tmp/fuzz/Setup.t.sol"))?,
+            Path::new(".medusa-gen/tmp/fuzz/Setup.t.sol")
+        );
+
+        assert!(state_path(root, Path::new("/projects/vault")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_sink_unchanged() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = Path::new("Setup.t.sol");
+
+        let mut sink = MergeSink::new(temp_dir.path());
+        sink.write(path, "contract Setup {\n}\n")?;
+
+        // only the user changed the file, it is kept as is
+        fs::write(
+            temp_dir.path().join(path),
+            "contract Setup {\n  uint256 x;\n}\n",
+        )?;
+        sink.write(path, "contract Setup {\n}\n")?;
+
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(path))?,
+            "contract Setup {\n  uint256 x;\n}\n"
+        );
+        assert_eq!(sink.report()[1].1, MergeStatus::Unchanged);
+        assert!(sink.summary().is_empty());
+
+        Ok(())
+    }
+}
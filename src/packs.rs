@@ -0,0 +1,255 @@
+use crate::abi::Target;
+
+use anyhow::{anyhow, Result};
+
+/// A standard with built-in properties
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Standard {
+    Erc20,
+    Erc4626,
+    Erc721,
+}
+
+impl Standard {
+    fn parse(name: &str) -> Result<Standard> {
+        match name.to_lowercase().as_str() {
+            "erc20" => Ok(Standard::Erc20),
+            "erc4626" => Ok(Standard::Erc4626),
+            "erc721" => Ok(Standard::Erc721),
+            _ => Err(anyhow!(
+                "Unknown property pack {}, expected erc20, erc4626 or erc721",
+                name
+            )),
+        }
+    }
+
+    /// The name used in the contract name (eg "ERC20" in "PropertiesERC20Token")
+    pub fn name(&self) -> &'static str {
+        match self {
+            Standard::Erc20 => "ERC20",
+            Standard::Erc4626 => "ERC4626",
+            Standard::Erc721 => "ERC721",
+        }
+    }
+}
+
+/// Built-in properties of a standard, checked against a target deployed in Setup
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyPack {
+    pub standard: Standard,
+    pub target: String,
+}
+
+impl PropertyPack {
+    /// Parse a `<standard>:<Target>` spec (eg "erc20:Token")
+    pub fn from_spec(spec: &str) -> Result<PropertyPack> {
+        let (standard, target) = spec
+            .split_once(':')
+            .filter(|(standard, target)| !standard.is_empty() && !target.is_empty())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Invalid property pack {}, expected <standard>:<Target> (eg erc20:Token)",
+                    spec
+                )
+            })?;
+
+        Ok(PropertyPack {
+            standard: Standard::parse(standard)?,
+            target: target.to_string(),
+        })
+    }
+
+    /// The suffix of the properties contract (eg "ERC20Token" for "PropertiesERC20Token")
+    pub fn name(&self) -> String {
+        format!("{}{}", self.standard.name(), self.target)
+    }
+
+    /// Whether the handlers of `target` keep their address inputs among the accounts summed by
+    /// this pack, the ERC20 sum of balances only holds if the tokens stay between them
+    pub fn clamps_holders(&self, target: &Target) -> bool {
+        self.standard == Standard::Erc20 && self.target == target.name
+    }
+
+    /// The properties contract body, with the fuzzer property `prefix`, `actors` adds the actors
+    /// to the accounts holding the target tokens
+    pub fn body(&self, targets: &[Target], actors: bool, prefix: &str) -> Result<String> {
+        let target = targets
+            .iter()
+            .find(|target| target.name == self.target)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown target {} for the {} property pack, pass it with --abi or --target",
+                    self.target,
+                    self.standard.name()
+                )
+            })?;
+
+        let instance = target.instance_name();
+
+        // the targets are summed as well, they can hold the tokens transferred to them
+        let targets_sum = targets
+            .iter()
+            .map(|holder| {
+                format!(
+                    "    _sum += {instance}.balanceOf(address({}));\n",
+                    holder.instance_name()
+                )
+            })
+            .collect::<String>();
+
+        Ok(match self.standard {
+            Standard::Erc20 => format!(
+                "  /// @notice The balances of {holders} sum up to the total supply, the
+  /// {instance} handlers only send tokens to {recipients}
+  function {prefix}erc20_sumOfBalancesEqualsTotalSupply() public view returns (bool) {{
+    uint256 _sum = {instance}.balanceOf(address(this));
+{targets_sum}{actors_sum}
+    return _sum == {instance}.totalSupply();
+  }}
+
+  /// @notice A transfer doesn't change the total supply
+  function {prefix}erc20_transferKeepsTotalSupply() public returns (bool) {{
+    uint256 _totalSupply = {instance}.totalSupply();
+
+    try {instance}.transfer({recipient}, {instance}.balanceOf(address(this)) / 2) {{
+      return {instance}.totalSupply() == _totalSupply;
+    }} catch {{
+      return true;
+    }}
+  }}
+",
+                holders = if actors {
+                    "this contract, the targets and the actors"
+                } else {
+                    "this contract and the targets"
+                },
+                recipients = if actors { "the actors" } else { "this contract" },
+                recipient = if actors { "actors[0]" } else { "address(this)" },
+                actors_sum = if actors {
+                    format!(
+                        "    for (uint256 _i; _i < actors.length; _i++) {{
+      _sum += {instance}.balanceOf(actors[_i]);
+    }}
+"
+                    )
+                } else {
+                    String::new()
+                },
+            ),
+            Standard::Erc4626 => format!(
+                "  /// @notice Converting assets to shares and back doesn't create assets
+  function {prefix}erc4626_convertRoundTrip() public view returns (bool) {{
+    uint256 _assets = {instance}.totalAssets();
+    return {instance}.convertToAssets({instance}.convertToShares(_assets)) <= _assets;
+  }}
+
+  /// @notice Previewing a deposit doesn't give more shares than the conversion
+  function {prefix}erc4626_previewDepositRoundsDown() public view returns (bool) {{
+    uint256 _assets = {instance}.totalAssets();
+    return {instance}.previewDeposit(_assets) <= {instance}.convertToShares(_assets);
+  }}
+
+  /// @notice Previewing a redeem doesn't give more assets than the conversion
+  function {prefix}erc4626_previewRedeemRoundsDown() public view returns (bool) {{
+    uint256 _shares = {instance}.totalSupply();
+    return {instance}.previewRedeem(_shares) <= {instance}.convertToAssets(_shares);
+  }}
+"
+            ),
+            Standard::Erc721 => format!(
+                "  /// @notice The owner of a token has a non-zero balance (checks the first 100 ids)
+  function {prefix}erc721_ownerHasBalance() public view returns (bool) {{
+    for (uint256 _id; _id < 100; _id++) {{
+      try {instance}.ownerOf(_id) returns (address _owner) {{
+        if ({instance}.balanceOf(_owner) == 0) return false;
+      }} catch {{}}
+    }}
+
+    return true;
+  }}
+
+  /// @notice Querying the balance of the zero address reverts
+  function {prefix}erc721_zeroAddressBalanceReverts() public view returns (bool) {{
+    try {instance}.balanceOf(address(0)) {{
+      return false;
+    }} catch {{
+      return true;
+    }}
+  }}
+"
+            ),
+        })
+    }
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn token() -> Target {
+        Target {
+            name: "Token".to_string(),
+            source: PathBuf::from("src/Token.sol"),
+            constructor: vec![],
+            functions: vec![],
+            getters: vec![],
+        }
+    }
+
+    #[test]
+    fn test_from_spec() -> Result<()> {
+        assert_eq!(
+            PropertyPack::from_spec("erc20:Token")?,
+            PropertyPack {
+                standard: Standard::Erc20,
+                target: "Token".to_string()
+            }
+        );
+        assert_eq!(
+            PropertyPack::from_spec("ERC4626:Vault")?.name(),
+            "ERC4626Vault"
+        );
+
+        assert!(PropertyPack::from_spec("erc20").is_err());
+        assert!(PropertyPack::from_spec("erc20:").is_err());
+        assert!(PropertyPack::from_spec("erc1155:Token").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_body() -> Result<()> {
+        let pack = PropertyPack::from_spec("erc20:Token")?;
+
+        let body = pack.body(&[token()], false, "property_")?;
+        assert!(body.contains(
+            "function property_erc20_sumOfBalancesEqualsTotalSupply() public view returns (bool) {
+    uint256 _sum = token.balanceOf(address(this));
+    _sum += token.balanceOf(address(token));
+
+    return _sum == token.totalSupply();"
+        ));
+        assert!(body.contains("token.transfer(address(this), "));
+        assert!(!body.contains("actors"));
+
+        let body = pack.body(&[token()], true, "property_")?;
+        assert!(body.contains("      _sum += token.balanceOf(actors[_i]);"));
+        assert!(body.contains("token.transfer(actors[0], "));
+
+        assert!(pack.clamps_holders(&token()));
+        assert!(!PropertyPack::from_spec("erc4626:Token")?.clamps_holders(&token()));
+
+        assert_eq!(
+            PropertyPack::from_spec("erc721:Nft")?
+                .body(&[token()], false, "property_")
+                .unwrap_err()
+                .to_string(),
+            "Unknown target Nft for the ERC721 property pack, pass it with --abi or --target"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+use crate::suite::GeneratedSuite;
+
+use anyhow::{Context, Result};
+use diffy::DiffOptions;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsStr;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What writing a file would do to the destination
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    New,
+    Modified(String),
+    Unchanged,
+}
+
+impl Status {
+    /// Compare a rendered content with the current one at `path`, if any
+    pub fn of(path: &Path, content: &str) -> Result<Status> {
+        if !path.exists() {
+            return Ok(Status::New);
+        }
+
+        let current =
+            fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+
+        Ok(if current == content {
+            Status::Unchanged
+        } else {
+            Status::Modified(current)
+        })
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Status::New => "new",
+            Status::Modified(_) => "modified",
+            Status::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// Describe what would be written in the project `root`: the file tree of the suite contracts, the
+/// configs outside of the suite, then a unified diff for every file which already exists with a
+/// different content
+pub fn preview(suite: &GeneratedSuite, root: &Path) -> Result<String> {
+    let mut output = String::new();
+    let mut diffs = String::new();
+
+    let _ = writeln!(output, "{}", suite.out.display());
+    write_tree(
+        &mut output,
+        &mut diffs,
+        &suite.contracts,
+        Path::new(""),
+        &suite.out,
+        root,
+        "",
+    )?;
+
+    for (path, content) in &suite.configs {
+        let status = Status::of(&root.join(path), content)?;
+        let _ = writeln!(output, "{} ({})", path.display(), status.label());
+        write_diff(&mut diffs, path, content, &status);
+    }
+
+    if !diffs.is_empty() {
+        let _ = write!(output, "\n{}", diffs);
+    }
+
+    Ok(output)
+}
+
+/// Recursively write the sorted entries of `dir`, files are annotated with the status of their
+/// `dest` in `root`
+fn write_tree(
+    output: &mut String,
+    diffs: &mut String,
+    files: &BTreeMap<PathBuf, String>,
+    dir: &Path,
+    dest: &Path,
+    root: &Path,
+    prefix: &str,
+) -> Result<()> {
+    // the files and directories directly in `dir`
+    let entries: BTreeSet<&OsStr> = files
+        .keys()
+        .filter_map(|path| path.strip_prefix(dir).ok())
+        .filter_map(|path| path.iter().next())
+        .collect();
+
+    for (i, name) in entries.iter().enumerate() {
+        let is_last = i + 1 == entries.len();
+        let path = dir.join(name);
+        let target = dest.join(name);
+
+        let _ = write!(
+            output,
+            "{}{} {}",
+            prefix,
+            if is_last { "└──" } else { "├──" },
+            name.to_string_lossy()
+        );
+
+        match files.get(&path) {
+            Some(content) => {
+                let status = Status::of(&root.join(&target), content)?;
+
+                let _ = writeln!(output, " ({})", status.label());
+                write_diff(diffs, &target, content, &status);
+            }
+            None => {
+                let _ = writeln!(output);
+                write_tree(
+                    output,
+                    diffs,
+                    files,
+                    &path,
+                    &target,
+                    root,
+                    &format!("{}{}", prefix, if is_last { "    " } else { "│   " }),
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Append the unified diff between the current and the rendered content of a modified file
+fn write_diff(output: &mut String, path: &Path, content: &str, status: &Status) {
+    if let Status::Modified(current) = status {
+        let name = path.display().to_string();
+        let patch = DiffOptions::new()
+            .set_original_filename(format!("a/{}", name))
+            .set_modified_filename(format!("b/{}", name))
+            .create_patch(current, content)
+            .to_string();
+
+        let _ = writeln!(output, "{}", patch);
+    }
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_status() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("Setup.t.sol");
+
+        assert_eq!(Status::of(&path, "new")?, Status::New);
+
+        fs::write(&path, "current")?;
+        assert_eq!(Status::of(&path, "current")?, Status::Unchanged);
+        assert_eq!(
+            Status::of(&path, "new")?,
+            Status::Modified(String::from("current"))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_preview() -> Result<()> {
+        let root = TempDir::new()?;
+        let dest = root.path().join("fuzz");
+        fs::create_dir_all(dest.join("handlers"))?;
+        fs::write(dest.join("handlers/HandlersA.t.sol"), "a\n")?;
+        fs::write(dest.join("Setup.t.sol"), "setup\n")?;
+
+        let mut suite = GeneratedSuite::new(Path::new("fuzz"));
+        suite
+            .contracts
+            .insert(PathBuf::from("handlers/HandlersA.t.sol"), "a\n".to_string());
+        suite.contracts.insert(
+            PathBuf::from("handlers/HandlersParent.t.sol"),
+            "parent\n".to_string(),
+        );
+        suite.contracts.insert(
+            PathBuf::from("Setup.t.sol"),
+            "setup\nnew line\n".to_string(),
+        );
+        suite
+            .configs
+            .insert(PathBuf::from("medusa.json"), "{}".to_string());
+
+        assert_eq!(
+            preview(&suite, root.path())?,
+            "fuzz
+├── Setup.t.sol (modified)
+└── handlers
+    ├── HandlersA.t.sol (unchanged)
+    └── HandlersParent.t.sol (new)
+medusa.json (new)
+
+--- a/fuzz/Setup.t.sol
++++ b/fuzz/Setup.t.sol
+@@ -1 +1,2 @@
+ setup
++new line
+
+"
+        );
+
+        // nothing is written
+        assert!(!dest.join("handlers/HandlersParent.t.sol").exists());
+        assert_eq!(fs::read_to_string(dest.join("Setup.t.sol"))?, "setup\n");
+
+        Ok(())
+    }
+}
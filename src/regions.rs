@@ -0,0 +1,330 @@
+use crate::error::{MedusaGenError, MedusaGenResult};
+use crate::sink::{DiskSink, Sink};
+
+use anyhow::{anyhow, Context, Result};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Start of a tool-owned region, followed by the region id (eg "// medusa-gen:begin imports")
+pub const BEGIN: &str = "// medusa-gen:begin";
+
+/// End of a tool-owned region, followed by the region id
+pub const END: &str = "// medusa-gen:end";
+
+/// The region wrapping the handler functions generated from a target ABI
+pub const HANDLERS: &str = "handlers";
+
+/// The region wrapping the contract declaration, with its parents
+pub const PARENTS: &str = "parents";
+
+/// Wrap lines of generated code in a region, the markers being indented with `indent`
+pub fn wrap(id: &str, indent: &str, content: &str) -> String {
+    format!(
+        "{indent}{BEGIN} {id}\n{content}{}{indent}{END} {id}\n",
+        if content.is_empty() || content.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        }
+    )
+}
+
+/// The marker line of a region (eg "// medusa-gen:begin imports"), if `line` is one
+fn marker<'a>(line: &'a str, kind: &str) -> Option<&'a str> {
+    line.trim()
+        .strip_prefix(kind)
+        .and_then(|id| id.strip_prefix(' '))
+        .map(str::trim)
+}
+
+/// A region of a source: its id and the byte range of its content, between the marker lines
+#[derive(Debug, Clone, PartialEq)]
+struct Region<'a> {
+    id: &'a str,
+    start: usize,
+    end: usize,
+}
+
+/// The regions of a source, in order. Regions can't be nested
+fn regions(source: &str) -> Result<Vec<Region<'_>>> {
+    let mut regions = Vec::new();
+    let mut open: Option<(&str, usize)> = None;
+    let mut position = 0;
+
+    for line in source.split_inclusive('\n') {
+        if let Some(id) = marker(line, BEGIN) {
+            if let Some((open_id, _)) = open {
+                return Err(anyhow!("Region {} starts inside region {}", id, open_id));
+            }
+
+            open = Some((id, position + line.len()));
+        } else if let Some(id) = marker(line, END) {
+            match open.take() {
+                Some((open_id, start)) if open_id == id => regions.push(Region {
+                    id,
+                    start,
+                    end: position,
+                }),
+                _ => return Err(anyhow!("Region {} ends without starting", id)),
+            }
+        }
+
+        position += line.len();
+    }
+
+    match open {
+        Some((id, _)) => Err(anyhow!("Region {} is not closed", id)),
+        None => Ok(regions),
+    }
+}
+
+/// Replace the content of the regions of `source` with the one of the same regions in
+/// `generated`, everything else is left untouched. Regions missing from `source` aren't added
+pub fn update(source: &str, generated: &str) -> Result<String> {
+    let generated_regions = regions(generated).context("Invalid generated regions")?;
+
+    let mut updated = String::with_capacity(source.len());
+    let mut position = 0;
+
+    for region in regions(source)? {
+        updated.push_str(&source[position..region.start]);
+
+        match generated_regions.iter().find(|new| new.id == region.id) {
+            Some(new) => updated.push_str(&generated[new.start..new.end]),
+            None => updated.push_str(&source[region.start..region.end]),
+        }
+
+        position = region.end;
+    }
+
+    updated.push_str(&source[position..]);
+
+    Ok(updated)
+}
+
+/// The source without the content of its regions, ie the code written by hand. The contract
+/// declaration of the parents region is kept, for the functions to be found in their contract
+pub fn strip(source: &str) -> Result<String> {
+    let mut stripped = String::with_capacity(source.len());
+    let mut position = 0;
+
+    for region in regions(source)? {
+        if region.id != PARENTS {
+            stripped.push_str(&source[position..region.start]);
+            position = region.end;
+        }
+    }
+
+    stripped.push_str(&source[position..]);
+
+    Ok(stripped)
+}
+
+/// What regenerating the regions of a file did
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegionStatus {
+    /// The file didn't exist, it is written as a whole
+    New,
+    /// At least one region changed
+    Updated,
+    Unchanged,
+}
+
+impl RegionStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            RegionStatus::New => "new",
+            RegionStatus::Updated => "updated",
+            RegionStatus::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// Write the files in a project root on disk, only rewriting the regions of the existing ones
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionSink {
+    root: PathBuf,
+    report: Vec<(PathBuf, RegionStatus)>,
+}
+
+impl RegionSink {
+    pub fn new(root: &Path) -> RegionSink {
+        RegionSink {
+            root: root.to_path_buf(),
+            report: Vec::new(),
+        }
+    }
+
+    /// The status of every file written, in order
+    pub fn report(&self) -> &[(PathBuf, RegionStatus)] {
+        &self.report
+    }
+
+    /// Describe the files which were created or had regions rewritten
+    pub fn summary(&self) -> String {
+        let mut output = String::new();
+
+        for (path, status) in &self.report {
+            if matches!(status, RegionStatus::New | RegionStatus::Updated) {
+                let _ = writeln!(output, "{} ({})", path.display(), status.label());
+            }
+        }
+
+        output
+    }
+}
+
+impl Sink for RegionSink {
+    fn exists(&self, path: &Path) -> bool {
+        self.root.join(path).exists()
+    }
+
+    fn write(&mut self, path: &Path, content: &str) -> MedusaGenResult<()> {
+        let target = self.root.join(path);
+
+        let status = if target.exists() {
+            let current = fs::read_to_string(&target).map_err(|source| MedusaGenError::Io {
+                path: target.clone(),
+                source,
+            })?;
+
+            let updated = update(&current, content).context(format!(
+                "Failed to update the regions of {}",
+                path.display()
+            ))?;
+
+            if updated == current {
+                RegionStatus::Unchanged
+            } else {
+                DiskSink::new(&self.root).write(path, &updated)?;
+                RegionStatus::Updated
+            }
+        } else {
+            DiskSink::new(&self.root).write(path, content)?;
+            RegionStatus::New
+        };
+
+        self.report.push((path.to_path_buf(), status));
+
+        Ok(())
+    }
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const GENERATED: &str = "// medusa-gen:begin imports
+import { HandlersA } from './HandlersA.t.sol';
+import { HandlersB } from './HandlersB.t.sol';
+// medusa-gen:end imports
+
+// medusa-gen:begin parents
+contract HandlersParent is HandlersA, HandlersB {
+// medusa-gen:end parents
+
+}";
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap(HANDLERS, "  ", "  function handler_a() public {}\n"),
+            "  // medusa-gen:begin handlers\n  function handler_a() public {}\n  // medusa-gen:end handlers\n"
+        );
+    }
+
+    #[test]
+    fn test_update() -> Result<()> {
+        let source = "// medusa-gen:begin imports
+import { HandlersA } from './HandlersA.t.sol';
+// medusa-gen:end imports
+import { Helpers } from './Helpers.sol';
+
+// medusa-gen:begin parents
+contract HandlersParent is HandlersA {
+// medusa-gen:end parents
+  // hand-written code
+}";
+
+        assert_eq!(
+            update(source, GENERATED)?,
+            "// medusa-gen:begin imports
+import { HandlersA } from './HandlersA.t.sol';
+import { HandlersB } from './HandlersB.t.sol';
+// medusa-gen:end imports
+import { Helpers } from './Helpers.sol';
+
+// medusa-gen:begin parents
+contract HandlersParent is HandlersA, HandlersB {
+// medusa-gen:end parents
+  // hand-written code
+}"
+        );
+
+        // without regions, nothing is updated
+        assert_eq!(update("contract A {}", GENERATED)?, "contract A {}");
+
+        assert!(update("// medusa-gen:begin imports\n", GENERATED).is_err());
+        assert!(update("// medusa-gen:end imports\n", GENERATED).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip() -> Result<()> {
+        assert_eq!(
+            strip(&GENERATED.replace("\n\n}", "\n  // hand-written code\n}"))?,
+            "// medusa-gen:begin imports
+// medusa-gen:end imports
+
+// medusa-gen:begin parents
+contract HandlersParent is HandlersA, HandlersB {
+// medusa-gen:end parents
+  // hand-written code
+}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_region_sink() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = Path::new("handlers/HandlersParent.t.sol");
+
+        let mut sink = RegionSink::new(temp_dir.path());
+        sink.write(path, GENERATED)?;
+
+        let edited = GENERATED.replace("\n\n}", "\n  // hand-written code\n}");
+        fs::write(temp_dir.path().join(path), &edited)?;
+
+        sink.write(path, &GENERATED.replace(", HandlersB", ""))?;
+        sink.write(path, &GENERATED.replace(", HandlersB", ""))?;
+
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join(path))?,
+            edited.replace(", HandlersB", "")
+        );
+        assert_eq!(
+            sink.report()
+                .iter()
+                .map(|(_, status)| *status)
+                .collect::<Vec<_>>(),
+            vec![
+                RegionStatus::New,
+                RegionStatus::Updated,
+                RegionStatus::Unchanged
+            ]
+        );
+        assert_eq!(
+            sink.summary(),
+            "handlers/HandlersParent.t.sol (new)\nhandlers/HandlersParent.t.sol (updated)\n"
+        );
+
+        Ok(())
+    }
+}
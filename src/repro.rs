@@ -0,0 +1,311 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// A call of a Medusa call sequence, with the block delays since the previous call
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CallSequenceElement {
+    pub call: Call,
+    #[serde(default)]
+    pub block_number_delay: u64,
+    #[serde(default)]
+    pub block_timestamp_delay: u64,
+}
+
+/// The transaction sent to the fuzzed contract, only the fields needed to replay it are kept
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Call {
+    pub from: String,
+    #[serde(default)]
+    pub value: Option<String>,
+    pub data: String,
+    pub data_abi_values: Option<DataAbiValues>,
+}
+
+/// The decoded calldata of a call
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DataAbiValues {
+    pub method_signature: String,
+    #[serde(default)]
+    pub input_values: Vec<Value>,
+}
+
+/// Load a call sequence written by Medusa in the corpus `test_results` directory
+pub fn load(path: &Path) -> Result<Vec<CallSequenceElement>> {
+    let content = fs::read_to_string(path)
+        .context(format!("Failed to read call sequence {}", path.display()))?;
+
+    let sequence: Vec<CallSequenceElement> = serde_json::from_str(&content)
+        .context(format!("Failed to parse call sequence {}", path.display()))?;
+
+    if sequence.is_empty() {
+        return Err(anyhow!("Empty call sequence in {}", path.display()));
+    }
+
+    Ok(sequence)
+}
+
+/// The body of the reproducer: a test replaying every call of the sequence from its sender,
+/// after the same block delays, then asserting the properties
+pub fn body(sequence: &[CallSequenceElement], properties: &[String]) -> String {
+    let mut output = String::from("  function test_repro() public {\n");
+
+    for (i, element) in sequence.iter().enumerate() {
+        if i > 0 {
+            let _ = writeln!(output);
+        }
+
+        if element.block_timestamp_delay > 0 {
+            let _ = writeln!(
+                output,
+                "    vm.warp(block.timestamp + {});",
+                element.block_timestamp_delay
+            );
+        }
+
+        if element.block_number_delay > 0 {
+            let _ = writeln!(
+                output,
+                "    vm.roll(block.number + {});",
+                element.block_number_delay
+            );
+        }
+
+        let value = element.call.value.as_deref().filter(|value| {
+            !value
+                .trim_start_matches("0x")
+                .trim_start_matches('0')
+                .is_empty()
+        });
+
+        if let Some(value) = value {
+            let _ = writeln!(
+                output,
+                "    vm.deal(address(this), address(this).balance + {});",
+                value
+            );
+        }
+
+        let _ = writeln!(
+            output,
+            "    vm.prank({});",
+            address_literal(&element.call.from)
+        );
+
+        let value = value.map_or(String::new(), |value| format!("{{value: {}}}", value));
+
+        match element.call.data_abi_values.as_ref().and_then(typed_call) {
+            Some((name, args)) => {
+                let _ = writeln!(output, "    this.{}{}({});", name, value, args.join(", "));
+            }
+            // tuples and arrays are replayed from the raw calldata
+            None => {
+                let _ = writeln!(
+                    output,
+                    "    (bool _success{}, ) = address(this).call{}(hex\"{}\");",
+                    i,
+                    value,
+                    element.call.data.trim_start_matches("0x")
+                );
+                let _ = writeln!(
+                    output,
+                    "    require(_success{}, \"call {} reverted\");",
+                    i, i
+                );
+            }
+        }
+    }
+
+    if !properties.is_empty() {
+        let _ = writeln!(output);
+    }
+
+    for property in properties {
+        let _ = writeln!(
+            output,
+            "    require({}(), \"{} failed\");",
+            property, property
+        );
+    }
+
+    let _ = writeln!(output, "  }}");
+
+    output
+}
+
+/// The function name and the Solidity literals of the arguments, if every argument is an
+/// elementary type
+fn typed_call(values: &DataAbiValues) -> Option<(String, Vec<String>)> {
+    let (name, types) = values.method_signature.strip_suffix(')')?.split_once('(')?;
+
+    if types.contains(['(', '[']) {
+        return None;
+    }
+
+    let types: Vec<&str> = if types.is_empty() {
+        vec![]
+    } else {
+        types.split(',').collect()
+    };
+
+    if types.len() != values.input_values.len() {
+        return None;
+    }
+
+    let args = types
+        .iter()
+        .zip(&values.input_values)
+        .map(|(kind, value)| literal(kind, value))
+        .collect::<Option<Vec<_>>>()?;
+
+    Some((name.to_string(), args))
+}
+
+/// The Solidity literal of an argument, as encoded by Medusa
+fn literal(kind: &str, value: &Value) -> Option<String> {
+    match (kind, value) {
+        ("bool", Value::Bool(value)) => Some(value.to_string()),
+        ("address", Value::String(value)) => Some(address_literal(value)),
+        ("string", Value::String(value)) => Some(string_literal(value)),
+        ("bytes", Value::String(value)) => {
+            Some(format!("hex\"{}\"", value.trim_start_matches("0x")))
+        }
+        (kind, Value::String(value)) if kind.starts_with("bytes") => Some(format!(
+            "{}(hex\"{}\")",
+            kind,
+            value.trim_start_matches("0x")
+        )),
+        // medusa encodes integers as decimal strings
+        (kind, Value::String(value)) if kind.starts_with("uint") || kind.starts_with("int") => {
+            let digits = value.strip_prefix('-').unwrap_or(value);
+
+            (!digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()))
+                .then(|| format!("{}({})", kind, value))
+        }
+        (kind, Value::Number(value)) if kind.starts_with("uint") || kind.starts_with("int") => {
+            Some(format!("{}({})", kind, value))
+        }
+        _ => None,
+    }
+}
+
+/// An address literal which doesn't require a checksum: short ones are used as is, others go
+/// through a 32 bytes literal
+fn address_literal(address: &str) -> String {
+    let digits = address.trim_start_matches("0x").trim_start_matches('0');
+
+    if digits.len() < 39 {
+        format!(
+            "address(0x{})",
+            if digits.is_empty() { "0" } else { digits }
+        )
+    } else {
+        format!("address(uint160(0x{:0>64}))", digits.to_lowercase())
+    }
+}
+
+/// A string literal, non-printable or quoting characters go through a hex literal
+fn string_literal(value: &str) -> String {
+    if value
+        .chars()
+        .all(|c| c.is_ascii() && !c.is_ascii_control() && c != '"' && c != '\\')
+    {
+        format!("\"{}\"", value)
+    } else {
+        let hex = value.bytes().fold(String::new(), |mut output, byte| {
+            let _ = write!(output, "{:02x}", byte);
+            output
+        });
+        format!("string(hex\"{}\")", hex)
+    }
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEQUENCE: &str = r#"[
+        {
+            "call": {
+                "from": "0x0000000000000000000000000000000000010000",
+                "to": "0xa647ff3c36cfab592509e13860ab8c4f28781a66",
+                "nonce": 0,
+                "value": "0x0",
+                "gasLimit": 12500000,
+                "gasPrice": "0x1",
+                "data": "0x",
+                "dataAbiValues": {
+                    "methodSignature": "handler_deposit(uint256,address,bool)",
+                    "inputValues": ["1000", "0xA647ff3c36cFab592509E13860ab8c4F28781a66", true]
+                }
+            },
+            "blockNumberDelay": 0,
+            "blockTimestampDelay": 0
+        },
+        {
+            "call": {
+                "from": "0x0000000000000000000000000000000000020000",
+                "value": "0x10",
+                "data": "0xdeadbeef",
+                "dataAbiValues": {
+                    "methodSignature": "handler_swap((uint256,uint256))",
+                    "inputValues": [{"a": "1", "b": "2"}]
+                }
+            },
+            "blockNumberDelay": 3,
+            "blockTimestampDelay": 60
+        }
+    ]"#;
+
+    #[test]
+    fn test_body() {
+        let sequence: Vec<CallSequenceElement> = serde_json::from_str(SEQUENCE).unwrap();
+
+        assert_eq!(
+            body(&sequence, &[String::from("property_solvency")]),
+            "  function test_repro() public {
+    vm.prank(address(0x10000));
+    this.handler_deposit(uint256(1000), address(uint160(0x000000000000000000000000a647ff3c36cfab592509e13860ab8c4f28781a66)), true);
+
+    vm.warp(block.timestamp + 60);
+    vm.roll(block.number + 3);
+    vm.deal(address(this), address(this).balance + 0x10);
+    vm.prank(address(0x20000));
+    (bool _success1, ) = address(this).call{value: 0x10}(hex\"deadbeef\");
+    require(_success1, \"call 1 reverted\");
+
+    require(property_solvency(), \"property_solvency failed\");
+  }
+"
+        );
+    }
+
+    #[test]
+    fn test_literal() {
+        assert_eq!(
+            literal("int8", &Value::from("-5")),
+            Some(String::from("int8(-5)"))
+        );
+        assert_eq!(
+            literal("bytes4", &Value::from("0xdeadbeef")),
+            Some(String::from("bytes4(hex\"deadbeef\")"))
+        );
+        assert_eq!(
+            literal("string", &Value::from("a \"quote\"")),
+            Some(String::from("string(hex\"61202271756f746522\")"))
+        );
+        assert_eq!(literal("uint256", &Value::Bool(true)), None);
+        assert_eq!(
+            address_literal("0x0000000000000000000000000000000000000000"),
+            "address(0x0)"
+        );
+    }
+}
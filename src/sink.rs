@@ -0,0 +1,244 @@
+use crate::error::{MedusaGenError, MedusaGenResult};
+
+use anyhow::anyhow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+/// Where a generated suite is written, every path being relative to the project root
+pub trait Sink {
+    /// Whether a file or a directory exists at `path`
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Write a file, replacing the existing one if any
+    fn write(&mut self, path: &Path, content: &str) -> MedusaGenResult<()>;
+}
+
+/// Drop the "." components, "./test/fuzz" and "test/fuzz" being the same directory
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+/// Write the files in a project root on disk
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskSink {
+    root: PathBuf,
+}
+
+impl DiskSink {
+    pub fn new(root: &Path) -> DiskSink {
+        DiskSink {
+            root: root.to_path_buf(),
+        }
+    }
+}
+
+impl Sink for DiskSink {
+    fn exists(&self, path: &Path) -> bool {
+        self.root.join(path).exists()
+    }
+
+    fn write(&mut self, path: &Path, content: &str) -> MedusaGenResult<()> {
+        let path = self.root.join(path);
+
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent).map_err(|source| MedusaGenError::Io {
+                path: parent.to_path_buf(),
+                source,
+            })?;
+        }
+
+        fs::write(&path, content).map_err(|source| MedusaGenError::Io { path, source })
+    }
+}
+
+/// Keep the files in memory, by normalized path
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MemorySink {
+    pub files: BTreeMap<PathBuf, String>,
+}
+
+impl MemorySink {
+    pub fn new() -> MemorySink {
+        MemorySink::default()
+    }
+
+    /// The content of a file, if written
+    pub fn get(&self, path: &Path) -> Option<&str> {
+        self.files.get(&normalize(path)).map(String::as_str)
+    }
+}
+
+impl Sink for MemorySink {
+    fn exists(&self, path: &Path) -> bool {
+        let path = normalize(path);
+
+        // a directory exists as soon as a file is written in it
+        self.files.keys().any(|file| file.starts_with(&path))
+    }
+
+    fn write(&mut self, path: &Path, content: &str) -> MedusaGenResult<()> {
+        self.files.insert(normalize(path), content.to_string());
+        Ok(())
+    }
+}
+
+/// Append the files to a tar archive, call [`TarSink::finish`] to get the archive back. A file
+/// written twice is appended twice, the last one being extracted. Paths out of the project root
+/// (absolute or going through "..") are rejected
+pub struct TarSink<W: Write> {
+    builder: tar::Builder<W>,
+    paths: Vec<PathBuf>,
+}
+
+impl<W: Write> TarSink<W> {
+    pub fn new(writer: W) -> TarSink<W> {
+        TarSink {
+            builder: tar::Builder::new(writer),
+            paths: Vec::new(),
+        }
+    }
+
+    /// Write the archive end and return the underlying writer
+    pub fn finish(self) -> MedusaGenResult<W> {
+        self.builder.into_inner().map_err(|error| {
+            MedusaGenError::Other(anyhow::Error::new(error).context("Failed to finish the archive"))
+        })
+    }
+}
+
+impl<W: Write> Sink for TarSink<W> {
+    fn exists(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        self.paths.iter().any(|file| file.starts_with(&path))
+    }
+
+    fn write(&mut self, path: &Path, content: &str) -> MedusaGenResult<()> {
+        let path = normalize(path);
+
+        // the entries are extracted relative to the project root, a suite out of it can't be
+        // archived
+        if path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+            return Err(MedusaGenError::Other(anyhow!(
+                "{} is out of the project root, the archived suite needs an --out inside it",
+                path.display()
+            )));
+        }
+
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+
+        self.builder
+            .append_data(&mut header, &path, content.as_bytes())
+            .map_err(|source| MedusaGenError::Io {
+                path: path.clone(),
+                source,
+            })?;
+
+        self.paths.push(path);
+
+        Ok(())
+    }
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_disk_sink() -> anyhow::Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut sink = DiskSink::new(temp_dir.path());
+
+        assert!(!sink.exists(Path::new("test/fuzz")));
+
+        sink.write(Path::new("./test/fuzz/Setup.t.sol"), "setup")?;
+
+        assert!(sink.exists(Path::new("test/fuzz")));
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("test/fuzz/Setup.t.sol"))?,
+            "setup"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_sink() -> anyhow::Result<()> {
+        let mut sink = MemorySink::new();
+
+        sink.write(Path::new("./test/fuzz/Setup.t.sol"), "setup")?;
+        sink.write(Path::new("medusa.json"), "{}")?;
+
+        assert!(sink.exists(Path::new("./test/fuzz")));
+        assert!(sink.exists(Path::new("medusa.json")));
+        assert!(!sink.exists(Path::new("echidna.yaml")));
+        assert_eq!(sink.get(Path::new("test/fuzz/Setup.t.sol")), Some("setup"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tar_sink() -> anyhow::Result<()> {
+        let mut sink = TarSink::new(Vec::new());
+
+        sink.write(Path::new("./test/fuzz/Setup.t.sol"), "setup")?;
+        assert!(sink.exists(Path::new("test/fuzz")));
+
+        let archive = sink.finish()?;
+        let mut archive = tar::Archive::new(archive.as_slice());
+        let mut entries = archive.entries()?;
+
+        let mut entry = entries.next().unwrap()?;
+        let mut content = String::new();
+        entry.read_to_string(&mut content)?;
+
+        assert_eq!(entry.path()?, Path::new("test/fuzz/Setup.t.sol"));
+        assert_eq!(content, "setup");
+        assert!(entries.next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tar_sink_out_of_root() -> anyhow::Result<()> {
+        let mut sink = TarSink::new(Vec::new());
+
+        for path in [
+            "../fuzz/Setup.t.sol",
+            "test/../../Setup.t.sol",
+            "/tmp/fuzz/Setup.t.sol",
+        ] {
+            assert_eq!(
+                sink.write(Path::new(path), "setup")
+                    .unwrap_err()
+                    .to_string(),
+                format!(
+                    "{} is out of the project root, the archived suite needs an --out inside it",
+                    path
+                )
+            );
+        }
+
+        // nothing was appended
+        assert!(!sink.exists(Path::new("")));
+        let archive = sink.finish()?;
+        assert!(tar::Archive::new(archive.as_slice())
+            .entries()?
+            .next()
+            .is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,470 @@
+use crate::cli::{
+    Args, Fuzzer, MedusaArgs, DEFAULT_ARTIFACTS, DEFAULT_LICENSE, DEFAULT_OUT, DEFAULT_SOLC,
+    DEFAULT_SRC,
+};
+use crate::collisions::Rename;
+use crate::error::{MedusaGenError, MedusaGenResult};
+use crate::letter_suffix;
+use crate::sink::{normalize, Sink};
+use crate::types::Contract;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Everything describing a suite to generate, independent of the command line: the handlers and
+/// properties, the targets deployed in Setup, the actors and the entry points. Create one with a
+/// [`SuiteBuilder`], or from the parsed [`Args`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuiteSpec {
+    /// The project root, which the other paths are relative to
+    pub root: PathBuf,
+    /// SPDX license identifier of the contracts
+    pub license: String,
+    /// Solidity version pragma of the contracts
+    pub solc: String,
+    /// The fuzz test folder, relative to the project root
+    pub out: PathBuf,
+    /// Directory of user templates overriding the embedded one
+    pub templates: Option<PathBuf>,
+    /// Handler contract name suffixes (eg "Vault" for "HandlersVault"), in addition to the
+    /// targets ones
+    pub handlers: Vec<String>,
+    /// Property contract name suffixes, in addition to the property packs ones
+    pub properties: Vec<String>,
+    /// Foundry artifacts of the targets, with their source in `src`
+    pub abis: Vec<PathBuf>,
+    pub src: PathBuf,
+    /// Targets as `<path>:<Contract>` (eg "src/Vault.sol:Vault"), their ABI read from `artifacts`
+    pub targets: Vec<String>,
+    pub artifacts: PathBuf,
+    /// Built-in properties, as `<standard>:<Target>` (eg "erc20:Token")
+    pub property_packs: Vec<String>,
+    /// Number of actors calling the handlers, if any
+    pub actors: Option<u8>,
+    pub fuzzer: Fuzzer,
+    /// Generate the Foundry invariant entry point too
+    pub foundry: bool,
+    /// The fuzzer configs campaign settings
+    pub campaign: MedusaArgs,
+    /// The (contract, function) names of the hand-written functions of the existing handlers,
+    /// which the generated handler functions must not collide with
+    pub existing_functions: Vec<(String, String)>,
+    /// Rename the generated handler functions colliding with another function instead of failing
+    pub rename_collisions: bool,
+}
+
+impl From<&Args> for SuiteSpec {
+    /// Without names, handlers (or properties) are named after their number, unless generated
+    /// from targets (or property packs)
+    fn from(args: &Args) -> Self {
+        let letters = |count: u8| (0..count as usize).map(letter_suffix).collect();
+
+        let handlers =
+            if args.handlers.is_empty() && args.abis.is_empty() && args.targets.is_empty() {
+                letters(args.nb_handlers())
+            } else {
+                args.handlers.clone()
+            };
+
+        let properties = if args.properties.is_empty() && args.property_packs.is_empty() {
+            letters(args.nb_properties())
+        } else {
+            args.properties.clone()
+        };
+
+        SuiteSpec {
+            root: PathBuf::from("."),
+            license: args.license().to_string(),
+            solc: args.solc().to_string(),
+            out: args.out().to_path_buf(),
+            templates: args.templates.clone(),
+            handlers,
+            properties,
+            abis: args.abis.clone(),
+            src: args.src().to_path_buf(),
+            targets: args.targets.clone(),
+            artifacts: args.artifacts().to_path_buf(),
+            property_packs: args.property_packs.clone(),
+            actors: args.actors,
+            fuzzer: args.fuzzer(),
+            foundry: args.foundry,
+            campaign: args.medusa.clone(),
+            existing_functions: vec![],
+            rename_collisions: args.rename_collisions,
+        }
+    }
+}
+
+/// Build a [`SuiteSpec`], every option not set defaults to the command line one
+#[derive(Debug, Clone)]
+pub struct SuiteBuilder {
+    spec: SuiteSpec,
+}
+
+impl Default for SuiteBuilder {
+    fn default() -> Self {
+        SuiteBuilder::new()
+    }
+}
+
+impl SuiteBuilder {
+    pub fn new() -> SuiteBuilder {
+        SuiteBuilder {
+            spec: SuiteSpec {
+                root: PathBuf::from("."),
+                license: DEFAULT_LICENSE.to_string(),
+                solc: DEFAULT_SOLC.to_string(),
+                out: PathBuf::from(DEFAULT_OUT),
+                templates: None,
+                handlers: vec![],
+                properties: vec![],
+                abis: vec![],
+                src: PathBuf::from(DEFAULT_SRC),
+                targets: vec![],
+                artifacts: PathBuf::from(DEFAULT_ARTIFACTS),
+                property_packs: vec![],
+                actors: None,
+                fuzzer: Fuzzer::default(),
+                foundry: false,
+                campaign: MedusaArgs::default(),
+                existing_functions: vec![],
+                rename_collisions: false,
+            },
+        }
+    }
+
+    pub fn with_root(mut self, root: &Path) -> Self {
+        self.spec.root = root.to_path_buf();
+        self
+    }
+
+    pub fn with_license(mut self, license: &str) -> Self {
+        self.spec.license = license.to_string();
+        self
+    }
+
+    pub fn with_solc(mut self, solc: &str) -> Self {
+        self.spec.solc = solc.to_string();
+        self
+    }
+
+    pub fn with_out(mut self, out: &Path) -> Self {
+        self.spec.out = out.to_path_buf();
+        self
+    }
+
+    pub fn with_templates(mut self, templates: &Path) -> Self {
+        self.spec.templates = Some(templates.to_path_buf());
+        self
+    }
+
+    pub fn with_handler(mut self, name: &str) -> Self {
+        self.spec.handlers.push(name.to_string());
+        self
+    }
+
+    pub fn with_property(mut self, name: &str) -> Self {
+        self.spec.properties.push(name.to_string());
+        self
+    }
+
+    pub fn with_abi(mut self, abi: &Path) -> Self {
+        self.spec.abis.push(abi.to_path_buf());
+        self
+    }
+
+    pub fn with_src(mut self, src: &Path) -> Self {
+        self.spec.src = src.to_path_buf();
+        self
+    }
+
+    pub fn with_target(mut self, target: &str) -> Self {
+        self.spec.targets.push(target.to_string());
+        self
+    }
+
+    pub fn with_artifacts(mut self, artifacts: &Path) -> Self {
+        self.spec.artifacts = artifacts.to_path_buf();
+        self
+    }
+
+    pub fn with_property_pack(mut self, pack: &str) -> Self {
+        self.spec.property_packs.push(pack.to_string());
+        self
+    }
+
+    pub fn with_actors(mut self, count: u8) -> Self {
+        self.spec.actors = Some(count);
+        self
+    }
+
+    pub fn with_fuzzer(mut self, fuzzer: Fuzzer) -> Self {
+        self.spec.fuzzer = fuzzer;
+        self
+    }
+
+    pub fn with_foundry(mut self, foundry: bool) -> Self {
+        self.spec.foundry = foundry;
+        self
+    }
+
+    pub fn with_campaign(mut self, campaign: MedusaArgs) -> Self {
+        self.spec.campaign = campaign;
+        self
+    }
+
+    pub fn with_existing_function(mut self, contract: &str, function: &str) -> Self {
+        self.spec
+            .existing_functions
+            .push((contract.to_string(), function.to_string()));
+        self
+    }
+
+    pub fn with_rename_collisions(mut self, rename: bool) -> Self {
+        self.spec.rename_collisions = rename;
+        self
+    }
+
+    pub fn build(self) -> SuiteSpec {
+        self.spec
+    }
+}
+
+/// A rendered suite, nothing is written until [`GeneratedSuite::write`]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GeneratedSuite {
+    /// The fuzz test folder, relative to the project root
+    pub out: PathBuf,
+    /// The contracts, by path relative to `out`
+    pub contracts: BTreeMap<PathBuf, String>,
+    /// The fuzzer configs, by path relative to the project root
+    pub configs: BTreeMap<PathBuf, String>,
+    /// The generated handler functions renamed to avoid a collision
+    pub renames: Vec<Rename>,
+}
+
+impl GeneratedSuite {
+    pub fn new(out: &Path) -> GeneratedSuite {
+        GeneratedSuite {
+            out: out.to_path_buf(),
+            ..Default::default()
+        }
+    }
+
+    /// Every file of the suite, by path relative to the project root
+    pub fn files(&self) -> impl Iterator<Item = (PathBuf, &str)> {
+        self.contracts
+            .iter()
+            .map(|(path, content)| (self.out.join(path), content.as_str()))
+            .chain(
+                self.configs
+                    .iter()
+                    .map(|(path, content)| (path.clone(), content.as_str())),
+            )
+    }
+
+    /// The content of a file, by path relative to the project root
+    pub fn get(&self, path: &Path) -> Option<&str> {
+        let path = normalize(path);
+
+        path.strip_prefix(normalize(&self.out))
+            .ok()
+            .and_then(|path| self.contracts.get(path))
+            .or_else(|| self.configs.get(&path))
+            .map(String::as_str)
+    }
+
+    /// Render a contract in `dir`, relative to `out`
+    pub(crate) fn insert_contract(
+        &mut self,
+        dir: &str,
+        contract: &Contract,
+    ) -> MedusaGenResult<()> {
+        let path = Path::new(dir).join(format!("{}.t.sol", contract.name));
+
+        if self.contracts.contains_key(&path) {
+            return Err(MedusaGenError::DestinationExists {
+                path: self.out.join(path),
+            });
+        }
+
+        let rendered = contract.render_contract()?;
+        self.contracts.insert(path, rendered);
+
+        Ok(())
+    }
+
+    /// Leave out the configs already in `sink`, returning their paths
+    pub fn keep_existing_configs(&mut self, sink: &dyn Sink) -> Vec<PathBuf> {
+        let existing: Vec<PathBuf> = self
+            .configs
+            .keys()
+            .filter(|path| sink.exists(path))
+            .cloned()
+            .collect();
+
+        for path in &existing {
+            self.configs.remove(path);
+        }
+
+        existing
+    }
+
+    /// Write the suite to a sink, the fuzz test folder and the configs must not exist unless
+    /// `overwrite` is set. Nothing is written if one does
+    pub fn write(&self, sink: &mut dyn Sink, overwrite: bool) -> MedusaGenResult<()> {
+        if !overwrite {
+            if let Some(path) = std::iter::once(&self.out)
+                .chain(self.configs.keys())
+                .find(|path| sink.exists(path))
+            {
+                return Err(MedusaGenError::DestinationExists { path: path.clone() });
+            }
+        }
+
+        for (path, content) in self.files() {
+            sink.write(&path, content)?;
+        }
+
+        Ok(())
+    }
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::MemorySink;
+    use clap::Parser;
+
+    #[test]
+    fn test_from_args() {
+        let spec = SuiteSpec::from(&Args::parse_from([
+            "medusa-gen",
+            "--nb-handlers",
+            "2",
+            "--nb-properties",
+            "1",
+        ]));
+
+        assert_eq!(spec.handlers, vec![String::from("A"), String::from("B")]);
+        assert_eq!(spec.properties, vec![String::from("A")]);
+        assert_eq!(
+            spec,
+            SuiteBuilder::new()
+                .with_handler("A")
+                .with_handler("B")
+                .with_property("A")
+                .build()
+        );
+
+        // the targets handlers replace the numbered ones
+        let spec = SuiteSpec::from(&Args::parse_from([
+            "medusa-gen",
+            "--target",
+            "Vault",
+            "--property-pack",
+            "erc20:Vault",
+        ]));
+
+        assert!(spec.handlers.is_empty());
+        assert!(spec.properties.is_empty());
+    }
+
+    #[test]
+    fn test_get() {
+        let mut suite = GeneratedSuite::new(Path::new("test/fuzz"));
+        suite
+            .contracts
+            .insert(PathBuf::from("Setup.t.sol"), String::from("setup"));
+        suite
+            .configs
+            .insert(PathBuf::from("medusa.json"), String::from("{}"));
+
+        assert_eq!(suite.get(Path::new("test/fuzz/Setup.t.sol")), Some("setup"));
+        assert_eq!(
+            suite.get(Path::new("./test/fuzz/Setup.t.sol")),
+            Some("setup")
+        );
+        assert_eq!(suite.get(Path::new("medusa.json")), Some("{}"));
+        assert_eq!(suite.get(Path::new("Setup.t.sol")), None);
+        assert_eq!(
+            suite.files().map(|(path, _)| path).collect::<Vec<_>>(),
+            vec![
+                PathBuf::from("test/fuzz/Setup.t.sol"),
+                PathBuf::from("medusa.json")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write() -> anyhow::Result<()> {
+        let mut sink = MemorySink::new();
+
+        let mut suite = GeneratedSuite::new(Path::new("./test/invariants/fuzz"));
+        suite.contracts.insert(
+            PathBuf::from("handlers/HandlersA.t.sol"),
+            String::from("handler"),
+        );
+        suite
+            .configs
+            .insert(PathBuf::from("medusa.json"), String::from("{}"));
+
+        suite.write(&mut sink, false)?;
+
+        assert_eq!(
+            sink.get(Path::new("test/invariants/fuzz/handlers/HandlersA.t.sol")),
+            Some("handler")
+        );
+        assert_eq!(sink.get(Path::new("medusa.json")), Some("{}"));
+
+        // an existing suite is only replaced with overwrite
+        assert!(matches!(
+            suite.write(&mut sink, false),
+            Err(MedusaGenError::DestinationExists { path }) if path == Path::new("./test/invariants/fuzz")
+        ));
+
+        suite.contracts.insert(
+            PathBuf::from("handlers/HandlersA.t.sol"),
+            String::from("updated"),
+        );
+        suite.write(&mut sink, true)?;
+
+        assert_eq!(
+            sink.get(Path::new("test/invariants/fuzz/handlers/HandlersA.t.sol")),
+            Some("updated")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keep_existing_configs() -> anyhow::Result<()> {
+        let mut sink = MemorySink::new();
+        sink.write(Path::new("medusa.json"), "{\"tuned\": true}")?;
+
+        let mut suite = GeneratedSuite::new(Path::new("fuzz"));
+        suite
+            .configs
+            .insert(PathBuf::from("medusa.json"), String::from("{}"));
+        suite
+            .configs
+            .insert(PathBuf::from("echidna.yaml"), String::from("seqLen: 100"));
+
+        assert_eq!(
+            suite.keep_existing_configs(&sink),
+            vec![PathBuf::from("medusa.json")]
+        );
+
+        suite.write(&mut sink, false)?;
+
+        assert_eq!(
+            sink.get(Path::new("medusa.json")),
+            Some("{\"tuned\": true}")
+        );
+        assert_eq!(sink.get(Path::new("echidna.yaml")), Some("seqLen: 100"));
+
+        Ok(())
+    }
+}
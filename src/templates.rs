@@ -0,0 +1,135 @@
+use crate::error::{MedusaGenError, MedusaGenResult};
+use crate::types::{Contract, ContractType};
+
+use anyhow::{Context, Result};
+use minijinja::value::{Serde, Value};
+use minijinja::Environment;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The contract types which can have a user-supplied template
+const TEMPLATED_TYPES: [ContractType; 8] = [
+    ContractType::Handler,
+    ContractType::Property,
+    ContractType::Setup,
+    ContractType::Actors,
+    ContractType::Ghosts,
+    ContractType::EntryPoint,
+    ContractType::Repro,
+    ContractType::FoundryEntryPoint,
+];
+
+/// User-supplied templates, rendered at runtime, by contract type. Types without one use the
+/// embedded contract.sol
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Templates {
+    sources: HashMap<&'static str, String>,
+}
+
+impl Templates {
+    /// Load the `handler.sol`, `property.sol`, `setup.sol`, `actors.sol`, `ghosts.sol`,
+    /// `entrypoint.sol`, `foundry.sol` and `repro.sol` found in `dir`
+    pub fn load(dir: &Path) -> Result<Templates> {
+        if !dir.is_dir() {
+            return Err(MedusaGenError::TemplateDirectoryNotFound {
+                path: dir.to_path_buf(),
+            }
+            .into());
+        }
+
+        let mut sources = HashMap::new();
+
+        for contract_type in TEMPLATED_TYPES {
+            let path = dir.join(contract_type.template_name());
+
+            if !path.exists() {
+                continue;
+            }
+
+            let source =
+                fs::read_to_string(&path).context(format!("Failed to read {}", path.display()))?;
+
+            // fail early on syntax errors, rather than when rendering
+            Environment::new()
+                .template_from_str(&source)
+                .context(format!("Invalid template {}", path.display()))?;
+
+            sources.insert(contract_type.template_name(), source);
+        }
+
+        Ok(Templates { sources })
+    }
+
+    /// The user-supplied template for a contract type, if any
+    pub fn get(&self, contract_type: &ContractType) -> Option<&String> {
+        self.sources.get(contract_type.template_name())
+    }
+}
+
+/// Render a contract with a runtime template, the contract fields are the template variables
+pub fn render(template: &str, contract: &Contract) -> MedusaGenResult<String> {
+    Environment::new()
+        .render_str(template, Value::from(Serde(contract)))
+        .map_err(|error| MedusaGenError::TemplateRender {
+            contract: contract.name.clone(),
+            source: Box::new(error),
+        })
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ContractBuilder;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        fs::write(temp_dir.path().join("handler.sol"), "// handler {{ name }}")?;
+        fs::write(temp_dir.path().join("unrelated.sol"), "// unrelated")?;
+
+        let templates = Templates::load(temp_dir.path())?;
+
+        assert_eq!(
+            templates.get(&ContractType::Handler),
+            Some(&String::from("// handler {{ name }}"))
+        );
+        assert_eq!(templates.get(&ContractType::Property), None);
+        assert_eq!(templates.get(&ContractType::Setup), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_invalid() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+
+        assert!(Templates::load(&temp_dir.path().join("missing")).is_err());
+
+        fs::write(temp_dir.path().join("setup.sol"), "{% if name %}")?;
+        assert!(Templates::load(temp_dir.path()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let contract = ContractBuilder::new()
+            .with_type(&ContractType::Handler)
+            .with_name(String::from("HandlersA"))
+            .build();
+
+        assert_eq!(
+            render(
+                "/// @notice {{ name }}\ncontract {{ name }}{% if parents != \"\" %} is {{ parents }}{% endif %} {}",
+                &contract
+            )?,
+            "/// @notice HandlersA\ncontract HandlersA is Setup {}"
+        );
+
+        Ok(())
+    }
+}
@@ -1,11 +1,15 @@
-use anyhow::{Context, Result};
+use crate::error::{MedusaGenError, MedusaGenResult};
+use crate::templates::{self, Templates};
+
+use anyhow::Result;
 use askama::Template;
+use serde::Serialize;
 use std::fs::File;
-use std::io::Write as WriteIO;
-use std::path::Path;
+use std::io::{ErrorKind, Write as WriteIO};
+use std::path::{Component, Path, PathBuf};
 
-/// The contract template,
-#[derive(Template, Debug, Clone, PartialEq)]
+/// The contract template, a user-supplied `template` takes precedence over the embedded one
+#[derive(Template, Serialize, Debug, Clone, PartialEq)]
 #[template(path = "contract.sol", escape = "none")]
 pub struct Contract {
     pub licence: String,
@@ -13,31 +17,55 @@ pub struct Contract {
     pub imports: String,
     pub name: String,
     pub parents: String,
+    pub body: String,
+    #[serde(skip)]
+    pub template: Option<String>,
 }
 
 impl Contract {
-    pub fn write_rendered_contract(&self, path: &Path) -> Result<()> {
-        let mut f = File::create_new(path.join(format!("{}{}", self.name, ".t.sol")))
-            .context(format!("Failed to create contract {}", self.name))?;
+    /// Render the contract with its user-supplied template, or the embedded one
+    pub fn render_contract(&self) -> MedusaGenResult<String> {
+        match &self.template {
+            Some(template) => templates::render(template, self),
+            None => self
+                .render()
+                .map_err(|error| MedusaGenError::TemplateRender {
+                    contract: self.name.clone(),
+                    source: Box::new(error),
+                }),
+        }
+    }
+
+    pub fn write_rendered_contract(&self, path: &Path) -> MedusaGenResult<()> {
+        let path = path.join(format!("{}{}", self.name, ".t.sol"));
 
-        let rendered = self
-            .render()
-            .context(format!("Fail to render {} contract", self.name))?;
+        let mut f = File::create_new(&path).map_err(|source| match source.kind() {
+            ErrorKind::AlreadyExists => MedusaGenError::DestinationExists { path: path.clone() },
+            _ => MedusaGenError::Io {
+                path: path.clone(),
+                source,
+            },
+        })?;
+
+        let rendered = self.render_contract()?;
 
         f.write_all(rendered.as_bytes())
-            .context(format!("Failed to write {}", self.name))?;
+            .map_err(|source| MedusaGenError::Io { path, source })?;
 
         Ok(())
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ContractBuilder {
     licence: String,
     solc: String,
     imports: String,
     name: String,
     parents: String,
+    body: String,
+    contract_type: Option<ContractType>,
+    templates: Templates,
 }
 
 impl ContractBuilder {
@@ -48,9 +76,24 @@ impl ContractBuilder {
             imports: String::from(""),
             name: String::from(""),
             parents: String::from(""),
+            body: String::from(""),
+            contract_type: None,
+            templates: Templates::default(),
         }
     }
 
+    pub fn with_licence(mut self, licence: String) -> Self {
+        self.licence = licence;
+        self
+    }
+
+    /// Set the compiler version, either a version or a pragma range (eg "0.8.23", "^0.8.0" or ">=0.8.0 <0.9.0")
+    pub fn with_solc(mut self, solc: String) -> Result<Self> {
+        validate_pragma(&solc)?;
+        self.solc = solc;
+        Ok(self)
+    }
+
     pub fn with_imports(mut self, imports: String) -> Self {
         self.imports = imports;
         self
@@ -66,13 +109,34 @@ impl ContractBuilder {
         self
     }
 
+    pub fn with_body(mut self, body: String) -> Self {
+        self.body = body;
+        self
+    }
+
+    /// Use the user-supplied templates, for the contract types having one
+    pub fn with_templates(mut self, templates: Templates) -> Self {
+        self.templates = templates;
+        self
+    }
+
     pub fn with_type(mut self, contract_type: &ContractType) -> Self {
-        self.imports = contract_type.import().to_owned();
+        self.contract_type = Some(*contract_type);
+        self.imports = contract_type.import();
         self.name = contract_type.name().to_owned();
         self.parents = contract_type.import_name().to_owned();
         self
     }
 
+    /// Inherit from another contract type than the default parent of the type (eg handlers
+    /// inheriting from Actors instead of Setup)
+    pub fn with_parent(mut self, parent: &ContractType) -> Self {
+        let contract_type = self.contract_type.unwrap_or(ContractType::Setup);
+        self.imports = contract_type.import_of(parent);
+        self.parents = parent.inherited_name().to_owned();
+        self
+    }
+
     pub fn build(self) -> Contract {
         Contract {
             licence: self.licence,
@@ -80,16 +144,25 @@ impl ContractBuilder {
             imports: self.imports,
             name: self.name,
             parents: self.parents,
+            body: self.body,
+            template: self
+                .contract_type
+                .and_then(|contract_type| self.templates.get(&contract_type).cloned()),
         }
     }
 }
 
 /// The type of contract to generate
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ContractType {
     Handler,
     Property,
     EntryPoint,
     Setup,
+    Actors,
+    Ghosts,
+    Repro,
+    FoundryEntryPoint,
 }
 
 /// Hold the contract type specific information
@@ -108,28 +181,508 @@ impl ContractType {
             ContractType::Property => "Properties",
             ContractType::EntryPoint => "FuzzTest",
             ContractType::Setup => "Setup",
+            ContractType::Actors => "Actors",
+            ContractType::Ghosts => "Ghosts",
+            ContractType::Repro => "Repro",
+            ContractType::FoundryEntryPoint => "FuzzTestFoundry",
         }
     }
 
-    pub fn import(&self) -> &'static str {
+    /// The file name of the user-supplied template for this type
+    pub fn template_name(&self) -> &'static str {
         match self {
-            ContractType::Handler => "import {Setup} from '../Setup.t.sol';\n",
-            ContractType::Property => {
-                "import {HandlersParent} from '../handlers/HandlersParent.t.sol';\n"
-            }
-            ContractType::EntryPoint => {
-                "import {PropertiesParent} from './properties/PropertiesParent.t.sol';\n"
-            }
-            ContractType::Setup => "",
+            ContractType::Handler => "handler.sol",
+            ContractType::Property => "property.sol",
+            ContractType::EntryPoint => "entrypoint.sol",
+            ContractType::Setup => "setup.sol",
+            ContractType::Actors => "actors.sol",
+            ContractType::Ghosts => "ghosts.sol",
+            ContractType::Repro => "repro.sol",
+            ContractType::FoundryEntryPoint => "foundry.sol",
+        }
+    }
+
+    /// The contract type this one inherits from, if any
+    pub fn parent(&self) -> Option<ContractType> {
+        match self {
+            ContractType::Handler => Some(ContractType::Setup),
+            ContractType::Property => Some(ContractType::Handler),
+            ContractType::EntryPoint => Some(ContractType::Property),
+            ContractType::Setup => None,
+            ContractType::Actors => Some(ContractType::Setup),
+            ContractType::Ghosts => Some(ContractType::Setup),
+            ContractType::Repro => Some(ContractType::EntryPoint),
+            ContractType::FoundryEntryPoint => Some(ContractType::Property),
         }
     }
 
+    /// The import statement of the parent contract, relative to this contract type directory
+    pub fn import(&self) -> String {
+        self.parent()
+            .map(|parent| self.import_of(&parent))
+            .unwrap_or_default()
+    }
+
+    /// The import statement of the contract inherited from `parent`, relative to this contract
+    /// type directory
+    pub fn import_of(&self, parent: &ContractType) -> String {
+        format!(
+            "import {{{}}} from '{}';\n",
+            parent.inherited_name(),
+            relative_import_path(
+                Path::new(self.directory_name()),
+                &Path::new(parent.directory_name())
+                    .join(format!("{}.t.sol", parent.inherited_name()))
+            )
+        )
+    }
+
     pub fn import_name(&self) -> &'static str {
+        self.parent().map_or("", |parent| parent.inherited_name())
+    }
+
+    /// The name of the contract children of this type inherit from
+    pub fn inherited_name(&self) -> &'static str {
         match self {
-            ContractType::Handler => "Setup",
-            ContractType::Property => "HandlersParent",
-            ContractType::EntryPoint => "PropertiesParent",
-            ContractType::Setup => "",
+            ContractType::Handler => "HandlersParent",
+            ContractType::Property => "PropertiesParent",
+            ContractType::EntryPoint => "FuzzTest",
+            ContractType::Setup => "Setup",
+            ContractType::Actors => "Actors",
+            ContractType::Ghosts => "Ghosts",
+            ContractType::Repro => "Repro",
+            ContractType::FoundryEntryPoint => "FuzzTestFoundry",
+        }
+    }
+}
+
+/// Solidity keywords and reserved words, which can't be used as identifiers
+const RESERVED_WORDS: &[&str] = &[
+    "abstract",
+    "address",
+    "after",
+    "alias",
+    "anonymous",
+    "apply",
+    "as",
+    "assembly",
+    "auto",
+    "bool",
+    "break",
+    "byte",
+    "bytes",
+    "calldata",
+    "case",
+    "catch",
+    "constant",
+    "constructor",
+    "continue",
+    "contract",
+    "copyof",
+    "days",
+    "default",
+    "define",
+    "delete",
+    "do",
+    "else",
+    "emit",
+    "enum",
+    "error",
+    "ether",
+    "event",
+    "external",
+    "fallback",
+    "false",
+    "final",
+    "fixed",
+    "for",
+    "function",
+    "gwei",
+    "hours",
+    "if",
+    "immutable",
+    "implements",
+    "import",
+    "in",
+    "indexed",
+    "inline",
+    "int",
+    "interface",
+    "internal",
+    "is",
+    "let",
+    "library",
+    "macro",
+    "mapping",
+    "match",
+    "memory",
+    "minutes",
+    "modifier",
+    "mutable",
+    "new",
+    "null",
+    "of",
+    "override",
+    "partial",
+    "payable",
+    "pragma",
+    "private",
+    "promise",
+    "public",
+    "pure",
+    "receive",
+    "reference",
+    "relocatable",
+    "return",
+    "returns",
+    "revert",
+    "sealed",
+    "seconds",
+    "sizeof",
+    "static",
+    "storage",
+    "string",
+    "struct",
+    "super",
+    "supports",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "type",
+    "typedef",
+    "typeof",
+    "ufixed",
+    "uint",
+    "unchecked",
+    "unicode",
+    "using",
+    "var",
+    "view",
+    "virtual",
+    "weeks",
+    "wei",
+    "while",
+    "years",
+];
+
+/// Check a name is a valid Solidity identifier and not a reserved word
+pub fn validate_identifier(name: &str) -> MedusaGenResult<()> {
+    let mut chars = name.chars();
+
+    let is_identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+
+    if !is_identifier {
+        return Err(MedusaGenError::InvalidIdentifier {
+            name: name.to_string(),
+        });
+    }
+
+    // elementary types with a size (eg uint8, bytes32) are reserved too
+    let is_sized_type = ["uint", "int", "bytes"].iter().any(|prefix| {
+        name.strip_prefix(prefix)
+            .is_some_and(|size| !size.is_empty() && size.chars().all(|c| c.is_ascii_digit()))
+    });
+
+    if RESERVED_WORDS.contains(&name) || is_sized_type {
+        return Err(MedusaGenError::ReservedKeyword {
+            name: name.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Check a list of contract names are valid identifiers, without duplicates
+pub fn validate_names(names: &[String]) -> MedusaGenResult<()> {
+    for (i, name) in names.iter().enumerate() {
+        validate_identifier(name)?;
+
+        if names[..i].contains(name) {
+            return Err(MedusaGenError::DuplicateName(name.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Check a solc version pragma, as a list of comparators (space separated) and ranges ("||" separated)
+pub fn validate_pragma(pragma: &str) -> MedusaGenResult<()> {
+    const OPERATORS: [&str; 7] = ["^", "~", ">=", "<=", ">", "<", "="];
+
+    let is_valid_comparator = |comparator: &str| {
+        let version = OPERATORS
+            .iter()
+            .find_map(|operator| comparator.strip_prefix(operator))
+            .unwrap_or(comparator);
+
+        let parts: Vec<&str> = version.split('.').collect();
+
+        parts.len() <= 3
+            && parts
+                .iter()
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+    };
+
+    let is_valid = pragma.split("||").all(|range| {
+        // solc accepts a space between an operator and its version (eg ">= 0.8.0")
+        let mut comparators: Vec<String> = Vec::new();
+        let mut operator = None;
+
+        for token in range.split_whitespace() {
+            if operator.is_none() && OPERATORS.contains(&token) {
+                operator = Some(token);
+            } else {
+                comparators.push(format!("{}{}", operator.take().unwrap_or_default(), token));
+            }
+        }
+
+        operator.is_none()
+            && !comparators.is_empty()
+            && comparators.iter().all(|c| is_valid_comparator(c))
+    });
+
+    if is_valid {
+        Ok(())
+    } else {
+        Err(MedusaGenError::InvalidPragma(pragma.to_string()))
+    }
+}
+
+/// Resolve the "." and ".." components of a path without touching the filesystem (eg "a/../b"
+/// gives "b"), the ".." leading a relative path are kept
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut components: Vec<Component> = Vec::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match components.last() {
+                Some(Component::Normal(_)) => {
+                    components.pop();
+                }
+                // the parent of the root is the root
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => components.push(component),
+            },
+            _ => components.push(component),
+        }
+    }
+
+    components.iter().collect()
+}
+
+/// Compute the Solidity import path of `to` as seen from a file living in `from_dir`
+/// (eg "handlers" and "Setup.t.sol" gives "../Setup.t.sol"). A `from_dir` climbing out of the
+/// common ancestor with ".." must be made absolute first, the directories it leaves being unknown
+pub fn relative_import_path(from_dir: &Path, to: &Path) -> String {
+    let from_dir = normalize_path(from_dir);
+    let to = normalize_path(to);
+    let from: Vec<Component> = from_dir.components().collect();
+    let to: Vec<Component> = to.components().collect();
+
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut segments: Vec<String> = vec![String::from(".."); from.len() - common];
+    segments.extend(
+        to[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+
+    let path = segments.join("/");
+
+    if path.starts_with("..") {
+        path
+    } else {
+        format!("./{}", path)
+    }
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_pragma() {
+        for pragma in [
+            "0.8.23",
+            "^0.8.0",
+            "~0.8.1",
+            ">=0.8.0 <0.9.0",
+            "0.8",
+            "^0.7.6 || ^0.8.0",
+            ">= 0.8.0",
+            ">= 0.8.0 < 0.9.0",
+            "^ 0.7.6 || >=0.8.0",
+        ] {
+            assert!(validate_pragma(pragma).is_ok(), "{}", pragma);
         }
+
+        for pragma in [
+            "",
+            "latest",
+            "0.8.",
+            "^0.8.a",
+            ">=0.8.0 <",
+            "0.8.0.1",
+            "^0.8 ||",
+            ">=",
+            "0.8.0 >=",
+            "> >= 0.8.0",
+        ] {
+            assert!(validate_pragma(pragma).is_err(), "{}", pragma);
+        }
+    }
+
+    #[test]
+    fn test_validate_identifier() {
+        for name in [
+            "Vault",
+            "AccessControl",
+            "_private",
+            "$dollar",
+            "V2",
+            "uint",
+        ] {
+            let result = validate_identifier(name);
+            assert_eq!(result.is_ok(), name != "uint", "{}", name);
+        }
+
+        assert_eq!(
+            validate_identifier("2Fast").unwrap_err().to_string(),
+            "2Fast is not a valid Solidity identifier"
+        );
+        assert_eq!(
+            validate_identifier("Access-Control")
+                .unwrap_err()
+                .to_string(),
+            "Access-Control is not a valid Solidity identifier"
+        );
+        assert!(validate_identifier("").is_err());
+        assert_eq!(
+            validate_identifier("bytes32").unwrap_err().to_string(),
+            "bytes32 is a reserved Solidity keyword"
+        );
+    }
+
+    #[test]
+    fn test_validate_names() {
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        assert!(validate_names(&names(&["Vault", "Oracle"])).is_ok());
+        assert!(validate_names(&[]).is_ok());
+        assert_eq!(
+            validate_names(&names(&["Vault", "Oracle", "Vault"]))
+                .unwrap_err()
+                .to_string(),
+            "Duplicate name: Vault"
+        );
+        assert!(validate_names(&names(&["Vault", "is"])).is_err());
+    }
+
+    #[test]
+    fn test_with_solc() -> Result<()> {
+        let contract = ContractBuilder::new()
+            .with_solc(String::from("0.8.23"))?
+            .build();
+        assert_eq!(contract.solc, "0.8.23");
+
+        let result = ContractBuilder::new().with_solc(String::from("0.8.23;"));
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Invalid solc version pragma: 0.8.23;"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_relative_import_path() {
+        assert_eq!(
+            relative_import_path(Path::new("handlers"), Path::new("Setup.t.sol")),
+            "../Setup.t.sol"
+        );
+        assert_eq!(
+            relative_import_path(
+                Path::new("properties"),
+                Path::new("handlers/HandlersParent.t.sol")
+            ),
+            "../handlers/HandlersParent.t.sol"
+        );
+        assert_eq!(
+            relative_import_path(
+                Path::new(""),
+                Path::new("properties/PropertiesParent.t.sol")
+            ),
+            "./properties/PropertiesParent.t.sol"
+        );
+        assert_eq!(
+            relative_import_path(Path::new("./a/b"), Path::new("a/c/D.sol")),
+            "../c/D.sol"
+        );
+        assert_eq!(
+            relative_import_path(Path::new("a/b/../c"), Path::new("a/./e/../d/E.sol")),
+            "../d/E.sol"
+        );
+        assert_eq!(
+            relative_import_path(Path::new("/p/x/handlers"), Path::new("/p/x/../src/A.sol")),
+            "../../src/A.sol"
+        );
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path(Path::new("./a/../b/.")), PathBuf::from("b"));
+        assert_eq!(
+            normalize_path(Path::new("../a/b/..")),
+            PathBuf::from("../a")
+        );
+        assert_eq!(
+            normalize_path(Path::new("a/../../b")),
+            PathBuf::from("../b")
+        );
+        assert_eq!(normalize_path(Path::new("/../a")), PathBuf::from("/a"));
+    }
+
+    #[test]
+    fn test_contract_type_import() {
+        assert_eq!(
+            ContractType::Handler.import(),
+            "import {Setup} from '../Setup.t.sol';\n"
+        );
+        assert_eq!(
+            ContractType::Property.import(),
+            "import {HandlersParent} from '../handlers/HandlersParent.t.sol';\n"
+        );
+        assert_eq!(
+            ContractType::EntryPoint.import(),
+            "import {PropertiesParent} from './properties/PropertiesParent.t.sol';\n"
+        );
+        assert_eq!(ContractType::Setup.import(), "");
+        assert_eq!(
+            ContractType::Actors.import(),
+            "import {Setup} from './Setup.t.sol';\n"
+        );
+    }
+
+    #[test]
+    fn test_with_parent() {
+        let contract = ContractBuilder::new()
+            .with_type(&ContractType::Handler)
+            .with_parent(&ContractType::Actors)
+            .build();
+
+        assert_eq!(
+            contract.imports,
+            "import {Actors} from '../Actors.t.sol';\n"
+        );
+        assert_eq!(contract.parents, "Actors");
     }
 }
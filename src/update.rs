@@ -0,0 +1,180 @@
+use crate::error::MedusaGenError;
+use crate::regions;
+
+use anyhow::{anyhow, Result};
+
+/// Add a child contract to an existing parent contract source: a new import line after the
+/// last import, and the child appended to the "is" list. Everything else is left untouched.
+pub fn add_to_parent(source: &str, parent: &str, child: &str) -> Result<String> {
+    let declaration = format!("contract {} ", parent);
+
+    let start = source
+        .find(&declaration)
+        .ok_or_else(|| anyhow!("No contract {} found", parent))?;
+    let end = start
+        + source[start..]
+            .find('{')
+            .ok_or_else(|| anyhow!("Invalid contract {} declaration", parent))?;
+
+    let inheritance = source[start + declaration.len()..end].trim();
+
+    let parents: Vec<&str> = inheritance
+        .strip_prefix("is")
+        .map(|list| list.split(',').map(str::trim).collect())
+        .unwrap_or_default();
+
+    if parents.contains(&child) {
+        return Err(MedusaGenError::AlreadyInherited {
+            parent: parent.to_string(),
+            child: child.to_string(),
+        }
+        .into());
+    }
+
+    let new_declaration = if parents.is_empty() {
+        format!("{}is {} ", declaration, child)
+    } else {
+        format!("{}{}, {} ", declaration, inheritance, child)
+    };
+
+    // the import goes after the last import of the header, or right before the contract (and its
+    // generated region, if any), separated from it by a blank line
+    let header = &source[..start];
+    let last_import = header
+        .match_indices("import ")
+        .filter(|(i, _)| *i == 0 || header.as_bytes()[i - 1] == b'\n')
+        .last()
+        .map(|(i, _)| i + header[i..].find('\n').map_or(header.len() - i, |n| n + 1));
+
+    let import_position = last_import
+        .or_else(|| header.rfind(&format!("{} {}\n", regions::BEGIN, regions::PARENTS)))
+        .unwrap_or(start);
+
+    let import = if last_import.is_some() {
+        format!("import {{ {} }} from './{}.t.sol';\n", child, child)
+    } else {
+        format!("import {{ {} }} from './{}.t.sol';\n\n", child, child)
+    };
+
+    let mut updated = String::with_capacity(source.len() + import.len() + child.len() + 2);
+    updated.push_str(&source[..import_position]);
+    updated.push_str(&import);
+    updated.push_str(&source[import_position..start]);
+    updated.push_str(&new_declaration);
+    updated.push_str(&source[end..]);
+
+    Ok(updated)
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARENT: &str = "// SPDX-License-Identifier: MIT
+pragma solidity 0.8.23;
+
+import { HandlersA } from './HandlersA.t.sol';
+import { HandlersB } from './HandlersB.t.sol';
+
+contract HandlersParent is HandlersA, HandlersB {
+  // hand-written code
+}";
+
+    #[test]
+    fn test_add_to_parent() -> Result<()> {
+        assert_eq!(
+            add_to_parent(PARENT, "HandlersParent", "HandlersFoo")?,
+            "// SPDX-License-Identifier: MIT
+pragma solidity 0.8.23;
+
+import { HandlersA } from './HandlersA.t.sol';
+import { HandlersB } from './HandlersB.t.sol';
+import { HandlersFoo } from './HandlersFoo.t.sol';
+
+contract HandlersParent is HandlersA, HandlersB, HandlersFoo {
+  // hand-written code
+}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_to_parent_no_parents() -> Result<()> {
+        let source = "pragma solidity 0.8.23;\n\ncontract PropertiesParent {\n\n}";
+
+        assert_eq!(
+            add_to_parent(source, "PropertiesParent", "PropertiesBar")?,
+            "pragma solidity 0.8.23;\n\nimport { PropertiesBar } from './PropertiesBar.t.sol';\n\ncontract PropertiesParent is PropertiesBar {\n\n}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_to_parent_regions() -> Result<()> {
+        let source = "// medusa-gen:begin imports
+import { HandlersA } from './HandlersA.t.sol';
+// medusa-gen:end imports
+
+// medusa-gen:begin parents
+contract HandlersParent is HandlersA {
+// medusa-gen:end parents
+
+}";
+
+        // the import and the parent stay in their generated region
+        assert_eq!(
+            add_to_parent(source, "HandlersParent", "HandlersFoo")?,
+            "// medusa-gen:begin imports
+import { HandlersA } from './HandlersA.t.sol';
+import { HandlersFoo } from './HandlersFoo.t.sol';
+// medusa-gen:end imports
+
+// medusa-gen:begin parents
+contract HandlersParent is HandlersA, HandlersFoo {
+// medusa-gen:end parents
+
+}"
+        );
+
+        let source = "// medusa-gen:begin parents
+contract HandlersParent is HandlersA {
+// medusa-gen:end parents
+}";
+
+        assert_eq!(
+            add_to_parent(source, "HandlersParent", "HandlersFoo")?,
+            "import { HandlersFoo } from './HandlersFoo.t.sol';
+
+// medusa-gen:begin parents
+contract HandlersParent is HandlersA, HandlersFoo {
+// medusa-gen:end parents
+}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_to_parent_already_inherited() {
+        assert_eq!(
+            add_to_parent(PARENT, "HandlersParent", "HandlersB")
+                .unwrap_err()
+                .to_string(),
+            "HandlersParent already inherits HandlersB"
+        );
+    }
+
+    #[test]
+    fn test_add_to_parent_missing_contract() {
+        assert_eq!(
+            add_to_parent(PARENT, "PropertiesParent", "PropertiesBar")
+                .unwrap_err()
+                .to_string(),
+            "No contract PropertiesParent found"
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use crate::check;
+use crate::cli::{Args, Fuzzer};
+use crate::config::CONFIG_FILE_NAME;
+use crate::suite::SuiteSpec;
+use crate::types::{validate_names, validate_pragma, ContractType};
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// The deployable contracts of the sources in `src`, recursively, as `<path>:<Contract>` target
+/// specs relative to the project `root` (eg "src/Vault.sol:Vault"). Tests and scripts are left
+/// aside
+pub fn discover_targets(root: &Path, src: &Path) -> Result<Vec<String>> {
+    let mut targets = Vec::new();
+    let dir = root.join(src);
+
+    if !dir.is_dir() {
+        return Ok(targets);
+    }
+
+    let mut names = fs::read_dir(&dir)
+        .context(format!("Failed to read {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    names.sort();
+
+    for name in names {
+        let path = src.join(&name);
+        let name = name.to_string_lossy();
+
+        if root.join(&path).is_dir() {
+            targets.extend(discover_targets(root, &path)?);
+        } else if name.ends_with(".sol") && !name.ends_with(".t.sol") && !name.ends_with(".s.sol") {
+            let source = fs::read_to_string(root.join(&path))
+                .context(format!("Failed to read {}", path.display()))?;
+
+            targets.extend(
+                check::deployable_contracts(&source)
+                    .into_iter()
+                    .map(|contract| format!("{}:{}", path.display(), contract)),
+            );
+        }
+    }
+
+    Ok(targets)
+}
+
+/// Comma separated names, empty ones are dropped
+fn split_names(answer: &str) -> Vec<String> {
+    answer
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// The name of a fuzzer on the command line (eg "medusa")
+fn fuzzer_name(fuzzer: Fuzzer) -> &'static str {
+    match fuzzer {
+        Fuzzer::Medusa => "medusa",
+        Fuzzer::Echidna => "echidna",
+        Fuzzer::Both => "both",
+    }
+}
+
+/// Ask the suite options one by one, reading the answers from `input`
+pub struct Wizard<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Wizard<R, W> {
+    pub fn new(input: R, output: W) -> Wizard<R, W> {
+        Wizard { input, output }
+    }
+
+    /// Ask a question until `parse` accepts the answer, an empty answer being `default`
+    fn ask<T>(
+        &mut self,
+        question: &str,
+        default: &str,
+        parse: impl Fn(&str) -> Result<T>,
+    ) -> Result<T> {
+        loop {
+            if default.is_empty() {
+                write!(self.output, "{}: ", question)?;
+            } else {
+                write!(self.output, "{} [{}]: ", question, default)?;
+            }
+            self.output.flush()?;
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return Err(anyhow!("No answer to \"{}\"", question));
+            }
+
+            let answer = match line.trim() {
+                "" => default,
+                answer => answer,
+            };
+
+            match parse(answer) {
+                Ok(value) => return Ok(value),
+                Err(error) => writeln!(self.output, "{:#}", error)?,
+            }
+        }
+    }
+
+    /// Ask the options of the suite to generate, `args` giving the defaults and `targets` the
+    /// contracts which can be fuzzed. None if the summary isn't confirmed
+    pub fn run(&mut self, args: &Args, targets: &[String]) -> Result<Option<Args>> {
+        let mut args = args.clone();
+
+        args.solc = Some(self.ask("Solidity version", args.solc(), |answer| {
+            validate_pragma(answer)?;
+            Ok(answer.to_string())
+        })?);
+
+        args.out = Some(self.ask(
+            "Output directory",
+            &args.out().display().to_string(),
+            |answer| Ok(PathBuf::from(answer)),
+        )?);
+
+        if !targets.is_empty() {
+            writeln!(self.output, "Contracts found in {}:", args.src().display())?;
+            for (i, target) in targets.iter().enumerate() {
+                writeln!(self.output, "  {}) {}", i + 1, target)?;
+            }
+
+            let default = targets
+                .iter()
+                .enumerate()
+                .filter(|(_, target)| args.targets.contains(target))
+                .map(|(i, _)| (i + 1).to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+
+            args.targets = self.ask(
+                "Contracts to fuzz, by number (eg 1,3), empty for none",
+                &default,
+                |answer| {
+                    split_names(answer)
+                        .iter()
+                        .map(|number| {
+                            number
+                                .parse::<usize>()
+                                .ok()
+                                .and_then(|n| targets.get(n.checked_sub(1)?))
+                                .cloned()
+                                .ok_or_else(|| anyhow!("{} is not a listed contract", number))
+                        })
+                        .collect()
+                },
+            )?;
+        }
+
+        let names = |answer: &str| {
+            let names = split_names(answer);
+            validate_names(&names)?;
+            Ok(names)
+        };
+
+        args.handlers = self.ask(
+            "Handler names (eg Vault,Oracle), empty for numbered ones unless fuzzing contracts",
+            &args.handlers.join(","),
+            names,
+        )?;
+        args.properties = self.ask(
+            "Property names (eg Solvency,AccessControl), empty for numbered ones",
+            &args.properties.join(","),
+            names,
+        )?;
+
+        args.actors = self.ask(
+            "Number of actors, 0 for none",
+            &args.actors.unwrap_or_default().to_string(),
+            |answer| {
+                let actors = answer
+                    .parse::<u8>()
+                    .map_err(|_| anyhow!("{} is not a number of actors", answer))?;
+                Ok((actors > 0).then_some(actors))
+            },
+        )?;
+
+        args.fuzzer = Some(self.ask(
+            "Fuzzer (medusa, echidna or both)",
+            fuzzer_name(args.fuzzer()),
+            |answer| Fuzzer::from_str(answer, true).map_err(|error| anyhow!(error)),
+        )?);
+
+        self.summary(&args)?;
+
+        let confirmed = self.ask(
+            &format!("Write the suite and {} ?", CONFIG_FILE_NAME),
+            "y",
+            |answer| match answer.to_lowercase().as_str() {
+                "y" | "yes" => Ok(true),
+                "n" | "no" => Ok(false),
+                _ => Err(anyhow!("Please answer y or n")),
+            },
+        )?;
+
+        Ok(confirmed.then_some(args))
+    }
+
+    /// Print the contracts and configs the answers give
+    fn summary(&mut self, args: &Args) -> Result<()> {
+        let spec = SuiteSpec::from(args);
+
+        let handlers: Vec<String> = spec
+            .targets
+            .iter()
+            .filter_map(|target| target.rsplit_once(':').map(|(_, name)| name))
+            .chain(spec.handlers.iter().map(String::as_str))
+            .map(|name| format!("{}{}", ContractType::Handler.name(), name))
+            .collect();
+        let properties: Vec<String> = spec
+            .properties
+            .iter()
+            .map(|name| format!("{}{}", ContractType::Property.name(), name))
+            .collect();
+
+        writeln!(self.output, "\nSummary:")?;
+        writeln!(self.output, "  solc: {}", spec.solc)?;
+        writeln!(self.output, "  out: {}", spec.out.display())?;
+        writeln!(self.output, "  handlers: {}", handlers.join(", "))?;
+        writeln!(self.output, "  properties: {}", properties.join(", "))?;
+        writeln!(
+            self.output,
+            "  actors: {}",
+            spec.actors.map_or(String::from("none"), |n| n.to_string())
+        )?;
+        writeln!(self.output, "  fuzzer: {}\n", fuzzer_name(spec.fuzzer))?;
+
+        Ok(())
+    }
+}
+
+// TESTS //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_discover_targets() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let src = temp_dir.path().join("src");
+        fs::create_dir_all(src.join("tokens"))?;
+
+        fs::write(
+            src.join("Vault.sol"),
+            "abstract contract Base {}\ninterface IVault {}\ncontract Vault is Base {}",
+        )?;
+        fs::write(src.join("tokens/Token.sol"), "contract Token {}")?;
+        fs::write(src.join("Vault.t.sol"), "contract VaultTest {}")?;
+
+        assert_eq!(
+            discover_targets(temp_dir.path(), Path::new("src"))?,
+            vec![
+                String::from("src/Vault.sol:Vault"),
+                String::from("src/tokens/Token.sol:Token"),
+            ]
+        );
+        assert!(discover_targets(temp_dir.path(), Path::new("missing"))?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run() -> Result<()> {
+        let targets = vec![
+            String::from("src/Vault.sol:Vault"),
+            String::from("src/Token.sol:Token"),
+        ];
+
+        // the default solc, an invalid then a valid target, invalid names are asked again
+        let input = "\n./test/fuzz\n3\n2\nRouter\n1Solvency\nSolvency\n3\nboth\ny\n";
+        let mut output = Vec::new();
+
+        let args = Wizard::new(Cursor::new(input), &mut output)
+            .run(&Args::parse_from(["medusa-gen"]), &targets)?
+            .unwrap();
+
+        assert_eq!(args.solc.as_deref(), Some("0.8.23"));
+        assert_eq!(args.out, Some(PathBuf::from("./test/fuzz")));
+        assert_eq!(args.targets, vec![String::from("src/Token.sol:Token")]);
+        assert_eq!(args.handlers, vec![String::from("Router")]);
+        assert_eq!(args.properties, vec![String::from("Solvency")]);
+        assert_eq!(args.actors, Some(3));
+        assert_eq!(args.fuzzer, Some(Fuzzer::Both));
+
+        let output = String::from_utf8(output)?;
+        assert!(output.contains("  2) src/Token.sol:Token\n"));
+        assert!(output.contains("3 is not a listed contract\n"));
+        assert!(output.contains("1Solvency is not a valid Solidity identifier\n"));
+        assert!(output.contains("  handlers: HandlersToken, HandlersRouter\n"));
+        assert!(output.contains("  properties: PropertiesSolvency\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_aborted() -> Result<()> {
+        let mut output = Vec::new();
+
+        // every default, then the summary isn't confirmed
+        let args = Wizard::new(Cursor::new("\n\n\n\n0\n\nn\n"), &mut output)
+            .run(&Args::parse_from(["medusa-gen"]), &[])?;
+
+        assert!(args.is_none());
+        assert!(String::from_utf8(output)?.contains("  handlers: HandlersA, HandlersB\n"));
+
+        // the input ends before every question is answered
+        assert!(Wizard::new(Cursor::new("\n"), Vec::new())
+            .run(&Args::parse_from(["medusa-gen"]), &[])
+            .is_err());
+
+        Ok(())
+    }
+}
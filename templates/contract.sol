@@ -1,7 +1,11 @@
 // SPDX-License-Identifier: {{ licence }}
 pragma solidity {{ solc }};
 
-{{ imports }}
-contract {{ name }} {% if parents != "" %}is {{parents}} {% endif %}{
-
-}
+{% if imports != "" %}// medusa-gen:begin imports
+{{ imports }}// medusa-gen:end imports
+{% endif %}
+{% if parents != "" %}// medusa-gen:begin parents
+{% endif %}contract {{ name }} {% if parents != "" %}is {{parents}} {% endif %}{
+{% if parents != "" %}// medusa-gen:end parents
+{% endif %}{% if body != "" %}{{ body }}{% else %}
+{% endif %}}